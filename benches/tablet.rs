@@ -0,0 +1,58 @@
+// compares the tablet vm with the bytecode interpreter on the real inputs in
+// `inputs/`, skipping any day whose input is missing. run with `cargo bench`
+
+extern crate aoc2017;
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use aoc2017::runner::input_path;
+use aoc2017::shared::{AppResult, read_input};
+use aoc2017::tablet::bytecode::Interpreter;
+use aoc2017::tablet::{self, Profile, Vm};
+
+
+const ROUNDS: u32 = 200;
+
+// the mean time of one run, after a warm up run
+fn time<F: FnMut() -> AppResult<u64>>(mut run: F) -> AppResult<(Duration, u64)> {
+    let steps = run()?;
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        run()?;
+    }
+    Ok((start.elapsed() / ROUNDS, steps))
+}
+
+fn bench(day: u32, profile: Profile) -> AppResult<()> {
+    let path = input_path(Path::new("inputs"), day);
+    if !path.exists() {
+        println!("day {}: skipped, no {}", day, path.display());
+        return Ok(());
+    }
+    let ops = tablet::parse(day, &read_input(&path)?)?;
+    let (vm, steps) = time(|| {
+        let mut vm = Vm::new(&ops, profile)?;
+        vm.run()?;
+        Ok(vm.steps())
+    })?;
+    let (bytecode, _) = time(|| {
+        let mut interpreter = Interpreter::new(&ops, profile)?;
+        interpreter.run()?;
+        Ok(interpreter.steps())
+    })?;
+    println!(
+        "day {}: {} steps, vm {:?}, bytecode {:?}, {:.1}x",
+        day, steps, vm, bytecode,
+        vm.as_secs_f64() / bytecode.as_secs_f64()
+    );
+    Ok(())
+}
+
+fn main() {
+    for &(day, profile) in &[(18, Profile::Sound), (23, Profile::Coprocessor)] {
+        if let Err(err) = bench(day, profile) {
+            println!("day {}: {}", day, err);
+        }
+    }
+}
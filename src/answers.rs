@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use runner::Outcome;
+use shared::AppResult;
+
+
+#[derive(Debug, PartialEq, Default)]
+pub struct Answers {
+    expected: BTreeMap<(u32, u32), String>,
+}
+
+impl Answers {
+    pub fn get(&self, day: u32, part: u32) -> Option<&str> {
+        self.expected.get(&(day, part)).map(|s| s.as_str())
+    }
+
+    pub fn has_day(&self, day: u32) -> bool {
+        self.get(day, 1).is_some() || self.get(day, 2).is_some()
+    }
+}
+
+
+// one `day part answer` entry per line; blank lines and `#` comments are skipped
+pub fn parse(input: &str) -> AppResult<Answers> {
+    let mut answers = Answers::default();
+    for (number, line) in input.split('\n').enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.splitn(3, char::is_whitespace);
+        let (day, part, answer) = match (fields.next(), fields.next(), fields.next()) {
+            (Some(day), Some(part), Some(answer)) => (day, part, answer.trim()),
+            _ => bail!("answers line {}: expected `day part answer`, found `{}`", number + 1, line),
+        };
+        let day = day.parse()
+            .map_err(|_| format_err!("answers line {}: invalid day `{}`", number + 1, day))?;
+        let part = match part {
+            "1" => 1,
+            "2" => 2,
+            _ => bail!("answers line {}: invalid part `{}`", number + 1, part),
+        };
+        if answers.expected.insert((day, part), answer.into()).is_some() {
+            bail!("answers line {}: duplicate answer for day {} part {}", number + 1, day, part);
+        }
+    }
+    Ok(answers)
+}
+
+
+#[derive(Debug, PartialEq)]
+pub enum Status {
+    Pass,
+    Fail(String),
+    Mismatch { expected: String, actual: String },
+    Unchecked(String),
+}
+
+impl Status {
+    pub fn is_failure(&self) -> bool {
+        match *self {
+            Status::Fail(_) | Status::Mismatch { .. } => true,
+            Status::Pass | Status::Unchecked(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Status::Pass => write!(f, "pass"),
+            Status::Fail(ref err) => write!(f, "FAIL      {}", err),
+            Status::Mismatch { ref expected, ref actual } => {
+                write!(f, "MISMATCH  expected {}, got {}", expected, actual)
+            },
+            Status::Unchecked(ref actual) => write!(f, "unchecked {}", actual),
+        }
+    }
+}
+
+
+pub fn check(outcome: &Outcome, expected: Option<&str>) -> Status {
+    match (&outcome.result, expected) {
+        (Err(err), _) => Status::Fail(err.clone()),
+        (Ok(answer), None) => Status::Unchecked(answer.to_string()),
+        (Ok(answer), Some(expected)) => {
+            let actual = answer.to_string();
+            if actual == expected {
+                Status::Pass
+            } else {
+                Status::Mismatch { expected: expected.into(), actual }
+            }
+        },
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::Answer;
+    use std::time::Duration;
+
+    fn outcome(result: Result<Answer, String>) -> Outcome {
+        Outcome { day: 1, part: 1, result, elapsed: Duration::new(0, 0) }
+    }
+
+    #[test]
+    fn test_parse() {
+        let answers = parse("# day part answer
+1 1 1044
+
+1 2 1054
+7 1 tknk").unwrap();
+        assert_eq!(answers.get(1, 1), Some("1044"));
+        assert_eq!(answers.get(1, 2), Some("1054"));
+        assert_eq!(answers.get(7, 1), Some("tknk"));
+        assert_eq!(answers.get(7, 2), None);
+        assert!(answers.has_day(7));
+        assert!(!answers.has_day(8));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("1 1").is_err());
+        assert!(parse("x 1 5").is_err());
+        assert!(parse("1 3 5").is_err());
+        assert!(parse("1 1 5\n1 1 6").is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        assert_eq!(check(&outcome(Ok(3u32.into())), Some("3")), Status::Pass);
+        assert_eq!(
+            check(&outcome(Ok(4u32.into())), Some("3")),
+            Status::Mismatch { expected: "3".into(), actual: "4".into() }
+        );
+        assert_eq!(check(&outcome(Ok(4u32.into())), None), Status::Unchecked("4".into()));
+        assert!(check(&outcome(Err("oops".into())), Some("3")).is_failure());
+    }
+}
@@ -0,0 +1,167 @@
+// cycle detection for simulations that step a state with a pure function.
+// every function here loops forever if the states never repeat
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+
+// the states reached from a start go through `mu` states that never come
+// back, then round a cycle of `lambda` states
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Cycle {
+    pub mu: usize,
+    pub lambda: usize,
+}
+
+impl Cycle {
+    // the smallest number of steps that reaches the same state as `steps`
+    pub fn reduce(&self, steps: u64) -> u64 {
+        let (mu, lambda) = (self.mu as u64, self.lambda as u64);
+        if steps < mu {
+            steps
+        } else {
+            mu + (steps - mu) % lambda
+        }
+    }
+}
+
+
+// Brent's algorithm: keeps two states, and usually steps fewer times than
+// Floyd's
+pub fn brent<T, F>(start: &T, mut step: F) -> Cycle
+    where T: PartialEq + Clone, F: FnMut(&T) -> T
+{
+    // find lambda by moving the tortoise up to the hare at each power of two
+    let (mut power, mut lambda) = (1, 1);
+    let mut tortoise = start.clone();
+    let mut hare = step(start);
+    while tortoise != hare {
+        if power == lambda {
+            tortoise = hare.clone();
+            power *= 2;
+            lambda = 0;
+        }
+        hare = step(&hare);
+        lambda += 1;
+    }
+
+    // then mu, with the hare lambda steps ahead
+    let mut tortoise = start.clone();
+    let mut hare = start.clone();
+    for _ in 0..lambda {
+        hare = step(&hare);
+    }
+    let mut mu = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        mu += 1;
+    }
+    Cycle { mu, lambda }
+}
+
+
+// Floyd's tortoise and hare: keeps two states
+pub fn floyd<T, F>(start: &T, mut step: F) -> Cycle
+    where T: PartialEq + Clone, F: FnMut(&T) -> T
+{
+    // the hare moves twice as fast, so they meet inside the cycle
+    let mut tortoise = step(start);
+    let mut hare = step(&tortoise);
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        let next = step(&hare);
+        hare = step(&next);
+    }
+
+    // a multiple of lambda apart, they meet again at the cycle's start
+    let mut mu = 0;
+    let mut tortoise = start.clone();
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        mu += 1;
+    }
+
+    let mut lambda = 1;
+    let mut hare = step(&tortoise);
+    while tortoise != hare {
+        hare = step(&hare);
+        lambda += 1;
+    }
+    Cycle { mu, lambda }
+}
+
+
+// remembers every state, but steps each one only once
+pub fn hashed<T, F>(start: &T, mut step: F) -> Cycle
+    where T: Eq + Hash + Clone, F: FnMut(&T) -> T
+{
+    let mut seen = HashMap::new();
+    let mut state = start.clone();
+    let mut index = 0;
+    loop {
+        if let Some(&first) = seen.get(&state) {
+            return Cycle { mu: first, lambda: index - first };
+        }
+        let next = step(&state);
+        seen.insert(state, index);
+        state = next;
+        index += 1;
+    }
+}
+
+
+// the state after `steps` steps, skipping whole times round the cycle
+pub fn state_after<T, F>(start: &T, steps: u64, mut step: F) -> T
+    where T: PartialEq + Clone, F: FnMut(&T) -> T
+{
+    let cycle = brent(start, &mut step);
+    let mut state = start.clone();
+    for _ in 0..cycle.reduce(steps) {
+        state = step(&state);
+    }
+    state
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a tail then a cycle, from most starts
+    fn step(x: &u64) -> u64 {
+        (x * x + 1) % 1019
+    }
+
+    #[test]
+    fn test_algorithms() {
+        for start in 0..200 {
+            let expected = hashed(&start, step);
+            assert_eq!(brent(&start, step), expected, "from {}", start);
+            assert_eq!(floyd(&start, step), expected, "from {}", start);
+        }
+        // a pure cycle, and a fixed point
+        assert_eq!(brent(&0, |x| (x + 1) % 7), Cycle { mu: 0, lambda: 7 });
+        assert_eq!(floyd(&3, |x| x / 2), Cycle { mu: 2, lambda: 1 });
+        assert_eq!(hashed(&3, |x| x / 2), Cycle { mu: 2, lambda: 1 });
+    }
+
+    #[test]
+    fn test_reduce() {
+        let cycle = Cycle { mu: 3, lambda: 4 };
+        assert_eq!(cycle.reduce(2), 2);
+        assert_eq!(cycle.reduce(3), 3);
+        assert_eq!(cycle.reduce(7), 3);
+        assert_eq!(cycle.reduce(1_000_000_000), 4);
+    }
+
+    #[test]
+    fn test_state_after() {
+        let mut state = 2;
+        for steps in 0..100 {
+            assert_eq!(state_after(&2, steps, step), state, "after {}", steps);
+            state = step(&state);
+        }
+    }
+}
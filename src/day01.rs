@@ -1,3 +1,4 @@
+use parsers::ParseError;
 use shared::AppResult;
 
 pub fn part1(input: &str) -> AppResult<u32> {
@@ -11,12 +12,23 @@ pub fn part2(input: &str) -> AppResult<u32> {
 }
 
 
+fn parse(input: &str) -> AppResult<Vec<u32>> {
+    input.chars()
+        .enumerate()
+        .map(|(column, c)| c.to_digit(10)
+            .ok_or_else(|| ParseError::new(1, 1, column + 1, input, "a digit").into())
+        )
+        .collect()
+}
+
+
 fn count_with_skip(input: &str, skip: usize) -> AppResult<u32> {
+    let digits = parse(input)?;
     let mut sum = 0;
 
-    for (a, b) in input.chars().zip(input.chars().cycle().skip(skip)) {
+    for (a, b) in digits.iter().zip(digits.iter().cycle().skip(skip)) {
         if a == b {
-            sum += a.to_digit(10).ok_or(format_err!("Not a digit"))?;
+            sum += a;
         }
     }
     Ok(sum)
@@ -43,4 +55,10 @@ mod tests {
         assert_eq!(part2("123123").unwrap(), 12);
         assert_eq!(part2("12131415").unwrap(), 4);
     }
+
+    #[test]
+    fn test_parse_error() {
+        let err = part1("12a4").unwrap_err().downcast::<ParseError>().unwrap();
+        assert_eq!(err, ParseError::new(1, 1, 3, "12a4", "a digit"));
+    }
 }
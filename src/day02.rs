@@ -1,14 +1,24 @@
 use std::cmp;
 use std::u32;
+use parsers::{map_lines, parse_words, ParseError};
 use shared::{AppResult};
 
 
+fn parse(input: &str) -> AppResult<Vec<Vec<u32>>> {
+    map_lines(input, |line, text| {
+        let values = parse_words(2, line, text, "a number")?;
+        if values.is_empty() {
+            return Err(ParseError::new(2, line, 1, text, "a number").into());
+        }
+        Ok(values)
+    })
+}
+
+
 pub fn part1(input: &str) -> AppResult<u32> {
     let mut sum = 0;
-    for row in input.split('\n') {
-        let values = row.split_whitespace()
-            .filter_map(|x| x.parse::<u32>().ok());
-        let (min, max) = values.fold(
+    for values in parse(input)? {
+        let (min, max) = values.into_iter().fold(
             (u32::max_value(), u32::min_value()),
             |(mn, mx), x| (cmp::min(mn, x), cmp::max(mx, x))
         );
@@ -20,10 +30,7 @@ pub fn part1(input: &str) -> AppResult<u32> {
 
 pub fn part2(input: &str) -> AppResult<u32> {
     let mut sum = 0;
-    for row in input.split('\n') {
-        let values = row.split_whitespace()
-            .filter_map(|x| x.parse::<u32>().ok())
-            .collect::<Vec<_>>();
+    for values in parse(input)? {
         sum += row_match(values);
     }
     Ok(sum)
@@ -63,4 +70,11 @@ mod tests {
 3 8 6 5";
     assert_eq!(part2(input).expect("failed"), 9);
     }
+
+    #[test]
+    fn test_parse_error() {
+        assert!(part1("5 1 9 5\n7 5 x").is_err());
+        let err = part1("5 1\n\n3 4").unwrap_err().downcast::<ParseError>().unwrap();
+        assert_eq!((err.line, err.column), (2, 1));
+    }
 }
@@ -1,9 +1,10 @@
+use parsers::parse_value;
 use shared::AppResult;
 use position::Position;
 use std::collections::HashMap;
 
 pub fn part1(input: &str) -> AppResult<u32> {
-    let position: i32 = input.parse()?;
+    let position: i32 = parse_value(3, 1, input, "a square number")?;
     let result = find_coors(position);
     Ok(distance(result))
 }
@@ -88,7 +89,7 @@ fn distance(position: Position) -> u32 {
 
 
 pub fn part2(input: &str) -> AppResult<u32> {
-    let max: i32 = input.parse()?;
+    let max: i32 = parse_value(3, 1, input, "a number")?;
     let mut values = HashMap::new();
     values.insert(Position::new(0, 0), 1);
     for position in Spiral::new() {
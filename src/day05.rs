@@ -1,10 +1,14 @@
+use parsers::{map_lines, parse_value};
 use shared::AppResult;
 
+
+fn parse(input: &str) -> AppResult<Vec<i32>> {
+    map_lines(input, |line, text| parse_value(5, line, text, "a jump offset"))
+}
+
+
 pub fn part1(input: &str) -> AppResult<u32> {
-    let mut instructions: Vec<i32> = input
-        .split_whitespace()
-        .filter_map(|x| x.parse().ok())
-        .collect();
+    let mut instructions = parse(input)?;
     let mut steps = 0;
     let mut pos: i32 = 0;
     while pos >= 0 && pos < instructions.len() as i32 {
@@ -20,10 +24,7 @@ pub fn part1(input: &str) -> AppResult<u32> {
 
 
 pub fn part2(input: &str) -> AppResult<u32> {
-    let mut instructions: Vec<i32> = input
-        .split_whitespace()
-        .filter_map(|x| x.parse().ok())
-        .collect();
+    let mut instructions = parse(input)?;
     let mut steps = 0;
     let mut pos: i32 = 0;
     while pos >= 0 && pos < instructions.len() as i32 {
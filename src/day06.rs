@@ -1,49 +1,103 @@
-use std::collections::HashMap;
+use cycle;
+use parsers::{parse_words, ParseError};
 use shared::AppResult;
 
 pub fn part1(input: &str) -> AppResult<u32> {
-    Ok(calculate(input).0)
+    Ok(calculate(&parse(input)?).0)
 }
 
 
 pub fn part2(input: &str) -> AppResult<u32> {
-    Ok(calculate(input).1)
+    Ok(calculate(&parse(input)?).1)
 }
 
 
-fn calculate(input: &str) -> (u32, u32) {
-    let mut banks: Vec<i32> = input
-        .split_whitespace()
-        .filter_map(|x| x.parse().ok())
-        .collect();
-    let len = banks.len();
-    let mut seen = HashMap::new();
-    let mut steps = 0;
-
-    while !seen.contains_key(&banks) {
-        let (maxpos, &blocks) = banks.iter()
-            .enumerate()
-            .max_by_key(|&(i, x)| (x, -(i as i32)))
-            .expect("banks empty");
-        // println!("{:?},    {}, {}", banks, blocks, maxpos);
+fn parse(input: &str) -> AppResult<Vec<u64>> {
+    let banks: Vec<u64> = parse_words(6, 1, input, "a block count")?;
+    if banks.is_empty() {
+        return Err(ParseError::new(6, 1, 1, input, "a block count").into());
+    }
+    // redistributing never changes the total, so no bank can overflow.
+    // the error points at the count that takes the total too high
+    let (mut total, mut rest) = (0u64, input);
+    for &blocks in &banks {
+        rest = rest.trim_start();
+        let column = input.len() - rest.len() + 1;
+        total = match total.checked_add(blocks) {
+            Some(total) => total,
+            None => return Err(ParseError::new(6, 1, column, input, "a smaller block count").into()),
+        };
+        rest = rest.trim_start_matches(|c: char| !c.is_whitespace());
+    }
+    Ok(banks)
+}
 
-        seen.insert(banks.clone(), steps);
-        let mut blocks = blocks;
 
-        banks[maxpos] = 0;
-        let mut pos = maxpos;
-        while blocks > 0 {
-            pos = (pos + 1) % len;
+// part 1 is the number of redistributions until a state repeats, part 2
+// the length of the loop
+fn calculate(banks: &[u64]) -> (u32, u32) {
+    let cycle = cycle::brent(&banks.to_vec(), |banks| redistribute(banks));
+    ((cycle.mu + cycle.lambda) as u32, cycle.lambda as u32)
+}
 
-            banks[pos] += 1;
-            blocks -= 1;
+// the bank with the most blocks; the lowest index wins a tie
+fn fullest(banks: &[u64]) -> usize {
+    let mut fullest = 0;
+    for (index, &blocks) in banks.iter().enumerate() {
+        if blocks > banks[fullest] {
+            fullest = index;
         }
-        steps += 1;
     }
-    // println!("{:?}\n\n{:?}", banks, seen);
-    let part1 = steps;
-    let part2 = steps - *seen.get(&banks).expect("banks should be seen");
-    (part1, part2)
+    fullest
+}
+
+// empties the fullest bank, gives every bank an equal share of its blocks,
+// then one more each to the banks after it until the remainder runs out
+pub fn redistribute(banks: &[u64]) -> Vec<u64> {
+    let len = banks.len();
+    let source = fullest(banks);
+    let (share, extra) = (banks[source] / len as u64, (banks[source] % len as u64) as usize);
+
+    let mut banks = banks.to_vec();
+    banks[source] = 0;
+    for (index, bank) in banks.iter_mut().enumerate() {
+        // 1 for the bank after the source, up to len for the source itself
+        let distance = (index + len - source - 1) % len + 1;
+        *bank += share + if distance <= extra { 1 } else { 0 };
+    }
+    banks
+}
+
+
+// every configuration from the start to the first repeat, one per line,
+// with the bank about to be emptied in brackets and the loop's start marked
+pub fn trace(input: &str) -> AppResult<String> {
+    let mut banks = parse(input)?;
+    let cycle = cycle::brent(&banks, |banks| redistribute(banks));
+    let mut out = String::new();
+    for step in 0..cycle.mu + cycle.lambda + 1 {
+        let source = fullest(&banks);
+        let cells: Vec<_> = banks.iter()
+            .enumerate()
+            .map(|(index, blocks)| if index == source {
+                format!("[{}]", blocks)
+            } else {
+                format!(" {} ", blocks)
+            })
+            .collect();
+        let note = if step == cycle.mu {
+            "  <- loop starts".to_string()
+        } else if step == cycle.mu + cycle.lambda {
+            format!("  <- same as {}", cycle.mu)
+        } else {
+            String::new()
+        };
+        out.push_str(format!("{:5} {}", step, cells.concat()).trim_end());
+        out.push_str(&note);
+        out.push('\n');
+        banks = redistribute(&banks);
+    }
+    Ok(out)
 }
 
 
@@ -60,4 +114,32 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2("0 2    7  0").expect("failed"), 4);
     }
+
+    #[test]
+    fn test_redistribute() {
+        assert_eq!(redistribute(&[0, 2, 7, 0]), vec![2, 4, 1, 2]);
+        // ties go to the lowest index
+        assert_eq!(redistribute(&[2, 4, 1, 2]), vec![3, 1, 2, 3]);
+        assert_eq!(redistribute(&[3, 1, 2, 3]), vec![0, 2, 3, 4]);
+        assert_eq!(redistribute(&[5]), vec![5]);
+        assert_eq!(
+            redistribute(&[1, 1 << 62, 0]),
+            vec![1 + (1 << 62) / 3, (1 << 62) / 3, (1 << 62) / 3 + 1]
+        );
+        let error = |input| parse(input).unwrap_err().downcast::<ParseError>().unwrap();
+        assert_eq!(error(""), ParseError::new(6, 1, 1, "", "a block count"));
+        assert_eq!(error("1 18446744073709551614  1").column, 25);
+        assert_eq!(error("18446744073709551615 1").column, 22);
+    }
+
+    #[test]
+    fn test_trace() {
+        assert_eq!(trace("0 2 7 0").unwrap(), "    0  0  2 [7] 0
+    1  2 [4] 1  2  <- loop starts
+    2 [3] 1  2  3
+    3  0  2  3 [4]
+    4  1  3 [4] 1
+    5  2 [4] 1  2  <- same as 1
+");
+    }
 }
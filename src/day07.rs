@@ -1,12 +1,12 @@
 use std::collections::{HashSet, HashMap};
 use std::str::{self, FromStr};
-use nom::{IResult, alphanumeric, digit, space};
+use nom::{alphanumeric, digit, space};
 
+use parsers::parse_lines;
 use shared::AppResult;
 
-pub fn part1(input: &str) -> AppResult<u32> {
-    println!("{:?}", bottom(input));
-    Ok(0)
+pub fn part1(input: &str) -> AppResult<String> {
+    bottom(input)
 }
 
 
@@ -37,11 +37,8 @@ fn balanced_children(
 
 
 pub fn part2(input: &str) -> AppResult<u32> {
-    let program_map: HashMap<_,_> = input.split('\n')
-        .filter_map(|line| match program(line.as_bytes()) {
-            IResult::Done(_, p) => Some(p),
-            _ => None,
-        })
+    let program_map: HashMap<_,_> = parse(input)?
+        .into_iter()
         .map(|p| (p.name.clone(), p))
         .collect();
 
@@ -122,13 +119,13 @@ named!(program <Program>,
 
 
 
+fn parse(input: &str) -> AppResult<Vec<Program>> {
+    parse_lines(7, input, "`name (weight)` optionally followed by ` -> children`", program)
+}
+
+
 fn bottom(input: &str) -> AppResult<String> {
-    let programs: Vec<Program> = input.split('\n')
-        .filter_map(|line| match program(line.as_bytes()) {
-            IResult::Done(_, p) => Some(p),
-            _ => None,
-        })
-        .collect();
+    let programs = parse(input)?;
     let mut seen_left = HashSet::new();
     let mut seen_right = HashSet::new();
     for program in programs {
@@ -175,6 +172,12 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_error() {
+        assert!(parse("pbga (66)\nxhth 57").is_err());
+        assert!(parse("fwft (72) -> ktlj,").is_err());
+    }
+
     #[test]
     fn test_bottom() {
         assert_eq!(bottom("pbga (66)
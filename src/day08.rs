@@ -1,19 +1,19 @@
-use std::collections::{HashMap};
+use std::collections::HashMap;
 use std::cmp;
 use std::str;
-use parsers::integer;
+use parsers::{long_integer, parse_lines};
 use shared::AppResult;
-use nom::{IResult, alpha, space};
+use nom::{alpha, space};
 
 
-#[derive(Debug, PartialEq)]
-enum Operation {
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Operation {
     Decrement,
     Increment,
 }
 
-#[derive(Debug, PartialEq)]
-enum ConditionOp {
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ConditionOp {
     Eq,
     Gt,
     Gte,
@@ -22,52 +22,273 @@ enum ConditionOp {
     Lte,
 }
 
-#[derive(Debug, PartialEq)]
-struct Program {
-    register: String,
-    operation: Operation,
-    value: i32,
-    condition_register: String,
-    condition_op: ConditionOp,
-    condition_value: i32,
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    // both round towards zero, as in rust
+    Div,
+    Mod,
 }
 
+#[derive(Debug, PartialEq, Clone)]
+pub enum Expr {
+    Value(i64),
+    Register(String),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Condition {
+    Compare(Expr, ConditionOp, Expr),
+    Not(Box<Condition>),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+// one line, e.g. `b inc a * 2 if a > 1 and not c == b`
+#[derive(Debug, PartialEq, Clone)]
+pub struct Program {
+    pub register: String,
+    pub operation: Operation,
+    pub value: Expr,
+    pub condition: Condition,
+}
+
+
+#[derive(Debug, Default)]
+pub struct Registers {
+    values: HashMap<String, i64>,
+    // the highest value ever held, kept up to date on every write
+    highest: i64,
+}
+
+impl Registers {
+    pub fn get(&self, register: &str) -> i64 {
+        self.values.get(register).cloned().unwrap_or(0)
+    }
+
+    pub fn set(&mut self, register: &str, value: i64) {
+        self.highest = cmp::max(self.highest, value);
+        match self.values.get_mut(register) {
+            Some(slot) => *slot = value,
+            None => {
+                self.values.insert(register.into(), value);
+            },
+        }
+    }
+
+    pub fn highest(&self) -> i64 {
+        self.highest
+    }
+
+    // the largest value now held by a register that has been written
+    pub fn largest(&self) -> Option<i64> {
+        self.values.values().max().cloned()
+    }
+}
+
+
+impl Expr {
+    pub fn evaluate(&self, registers: &Registers) -> AppResult<i64> {
+        match *self {
+            Expr::Value(value) => Ok(value),
+            Expr::Register(ref register) => Ok(registers.get(register)),
+            Expr::Neg(ref expr) => expr.evaluate(registers)?
+                .checked_neg()
+                .ok_or_else(|| format_err!("overflow negating {:?}", expr)),
+            Expr::Binary(op, ref left, ref right) => {
+                let (x, y) = (left.evaluate(registers)?, right.evaluate(registers)?);
+                if y == 0 && (op == BinaryOp::Div || op == BinaryOp::Mod) {
+                    bail!("division by zero");
+                }
+                let result = match op {
+                    BinaryOp::Add => x.checked_add(y),
+                    BinaryOp::Sub => x.checked_sub(y),
+                    BinaryOp::Mul => x.checked_mul(y),
+                    BinaryOp::Div => x.checked_div(y),
+                    BinaryOp::Mod => x.checked_rem(y),
+                };
+                result.ok_or_else(|| format_err!("overflow in {} {:?} {}", x, op, y))
+            },
+        }
+    }
+}
+
+impl Condition {
+    // `and` and `or` only evaluate their right side when they need to
+    pub fn evaluate(&self, registers: &Registers) -> AppResult<bool> {
+        match *self {
+            Condition::Compare(ref left, op, ref right) => Ok(compare(
+                left.evaluate(registers)?,
+                op,
+                right.evaluate(registers)?,
+            )),
+            Condition::Not(ref condition) => Ok(!condition.evaluate(registers)?),
+            Condition::And(ref left, ref right) => Ok(left.evaluate(registers)? && right.evaluate(registers)?),
+            Condition::Or(ref left, ref right) => Ok(left.evaluate(registers)? || right.evaluate(registers)?),
+        }
+    }
+}
+
+impl Program {
+    pub fn execute(&self, registers: &mut Registers) -> AppResult<()> {
+        if self.condition.evaluate(registers)? {
+            let value = self.value.evaluate(registers)?;
+            let current = registers.get(&self.register);
+            let result = match self.operation {
+                Operation::Increment => current.checked_add(value),
+                Operation::Decrement => current.checked_sub(value),
+            };
+            match result {
+                Some(result) => registers.set(&self.register, result),
+                None => bail!("overflow updating {}", self.register),
+            }
+        }
+        Ok(())
+    }
+}
+
+
+named!(gap <()>,
+    map!(opt!(complete!(space)), |_| ())
+);
+
+named!(register <String>,
+    map!(map_res!(alpha, str::from_utf8), String::from)
+);
+
+named!(atom <Expr>,
+    alt!(
+        map!(long_integer, Expr::Value) |
+        map!(register, Expr::Register) |
+        delimited!(pair!(tag!("("), gap), expr, pair!(gap, tag!(")"))) |
+        map!(preceded!(tag!("-"), atom), |e| Expr::Neg(Box::new(e)))
+    )
+);
+
+named!(product <Expr>,
+    do_parse!(
+        first: atom >>
+        expr: fold_many0!(
+            complete!(pair!(
+                preceded!(gap, alt!(
+                    tag!("*") => {|_| BinaryOp::Mul} |
+                    tag!("/") => {|_| BinaryOp::Div} |
+                    tag!("%") => {|_| BinaryOp::Mod}
+                )),
+                preceded!(gap, atom)
+            )),
+            first,
+            |left, (op, right)| Expr::Binary(op, Box::new(left), Box::new(right))
+        ) >>
+        (expr)
+    )
+);
+
+// a sum of products, e.g. `a + 2 * (b - 1)`
+named!(pub expr <Expr>,
+    do_parse!(
+        first: product >>
+        expr: fold_many0!(
+            complete!(pair!(
+                preceded!(gap, alt!(
+                    tag!("+") => {|_| BinaryOp::Add} |
+                    tag!("-") => {|_| BinaryOp::Sub}
+                )),
+                preceded!(gap, product)
+            )),
+            first,
+            |left, (op, right)| Expr::Binary(op, Box::new(left), Box::new(right))
+        ) >>
+        (expr)
+    )
+);
+
+named!(condition_op <ConditionOp>,
+    alt!(
+        tag!(">=") => {|_| ConditionOp::Gte} |
+        tag!(">") => {|_| ConditionOp::Gt} |
+        tag!("<=") => {|_| ConditionOp::Lte} |
+        tag!("<") => {|_| ConditionOp::Lt} |
+        tag!("!=") => {|_| ConditionOp::Ne} |
+        tag!("==") => {|_| ConditionOp::Eq}
+    )
+);
+
+named!(comparison <Condition>,
+    do_parse!(
+        left: expr >>
+        gap >>
+        op: condition_op >>
+        gap >>
+        right: expr >>
+        (Condition::Compare(left, op, right))
+    )
+);
+
+// `not` binds tighter than `and`, which binds tighter than `or`
+named!(negation <Condition>,
+    alt!(
+        complete!(do_parse!(
+            tag!("not") >>
+            space >>
+            condition: negation >>
+            (Condition::Not(Box::new(condition)))
+        )) |
+        complete!(comparison) |
+        delimited!(pair!(tag!("("), gap), condition, pair!(gap, tag!(")")))
+    )
+);
+
+named!(conjunction <Condition>,
+    do_parse!(
+        first: negation >>
+        condition: fold_many0!(
+            complete!(preceded!(delimited!(gap, tag!("and"), space), negation)),
+            first,
+            |left, right| Condition::And(Box::new(left), Box::new(right))
+        ) >>
+        (condition)
+    )
+);
+
+named!(pub condition <Condition>,
+    do_parse!(
+        first: conjunction >>
+        condition: fold_many0!(
+            complete!(preceded!(delimited!(gap, tag!("or"), space), conjunction)),
+            first,
+            |left, right| Condition::Or(Box::new(left), Box::new(right))
+        ) >>
+        (condition)
+    )
+);
 
 named!(program <Program>,
     do_parse!(
-        register: map_res!(alpha, str::from_utf8) >>
+        register: register >>
         space >>
         op: alt!(
             tag!("inc") => {|_| Operation::Increment} |
             tag!("dec") => {|_| Operation::Decrement}
         ) >>
         space >>
-        value: integer >>
+        value: expr >>
         tag!(" if ") >>
-        condition_register: map_res!(alpha, str::from_utf8) >>
-        space >>
-        condition_op: alt!(
-            tag!(">=") => {|_| ConditionOp::Gte} |
-            tag!(">") => {|_| ConditionOp::Gt} |
-            tag!("<=") => {|_| ConditionOp::Lte} |
-            tag!("<") => {|_| ConditionOp::Lt} |
-            tag!("!=") => {|_| ConditionOp::Ne} |
-            tag!("==") => {|_| ConditionOp::Eq}
-        ) >>
-        space >>
-        condition_value: integer >>
+        condition: condition >>
         (Program {
-            register: register.into(),
+            register,
             operation: op,
-            value: value,
-            condition_register: condition_register.into(),
-            condition_op: condition_op,
-            condition_value: condition_value,
+            value,
+            condition,
         })
     )
 );
 
-fn condition(left: i32, op: ConditionOp, right: i32) -> bool {
+fn compare(left: i64, op: ConditionOp, right: i64) -> bool {
     use self::ConditionOp::*;
     match op {
         Eq => left == right,
@@ -79,52 +300,29 @@ fn condition(left: i32, op: ConditionOp, right: i32) -> bool {
     }
 }
 
-pub fn process(input: &str) -> AppResult<(u32, u32)> {
-    let mut registers = HashMap::new();
-    let mut maxduring = 0;
+pub fn parse(input: &str) -> AppResult<Vec<Program>> {
+    parse_lines(8, input, "`reg inc|dec expr if condition`", program)
+}
 
-    for line in input.split('\n') {
-        // println!("::{}::", line);
-        let instruction = match program(line.as_bytes()) {
-            IResult::Done(_, instruction) => instruction,
-            err => bail!("Failed to parse instruction: {:?}", err),
-        };
-        let &condition_register_value = registers
-            .get(&instruction.condition_register)
-            .unwrap_or(&0);
-        if condition(
-                condition_register_value,
-                instruction.condition_op,
-                instruction.condition_value,
-            ) {
-
-            let mut register_value = registers
-                .entry(instruction.register)
-                .or_insert(0);
-            match instruction.operation {
-                Operation::Increment => {*register_value += instruction.value}
-                Operation::Decrement => {*register_value -= instruction.value}
-            }
-        }
-        maxduring = match registers.values().max() {
-            Some(&n) => cmp::max(maxduring, n),
-            None => maxduring,
-        };
+pub fn process(input: &str) -> AppResult<(i64, i64)> {
+    let mut registers = Registers::default();
+    for (line, instruction) in parse(input)?.iter().enumerate() {
+        instruction.execute(&mut registers)
+            .map_err(|err| format_err!("line {}: {}", line + 1, err))?;
     }
-    let maxend = match registers.values().max() {
-        Some(&n) => n as u32,
+    match registers.largest() {
+        Some(largest) => Ok((largest, registers.highest())),
         None => bail!("no registers"),
-    };
-    Ok((maxend, maxduring as u32))
+    }
 }
 
 
-pub fn part1(input: &str) -> AppResult<u32> {
+pub fn part1(input: &str) -> AppResult<i64> {
     process(input).map(|(x, _)| x)
 }
 
 
-pub fn part2(input: &str) -> AppResult<u32> {
+pub fn part2(input: &str) -> AppResult<i64> {
     process(input).map(|(_, y)| y)
 }
 
@@ -136,6 +334,21 @@ mod tests {
     use super::*;
     use nom::IResult;
 
+    fn register(name: &str) -> Expr {
+        Expr::Register(name.into())
+    }
+
+    fn binary(op: BinaryOp, left: Expr, right: Expr) -> Expr {
+        Expr::Binary(op, Box::new(left), Box::new(right))
+    }
+
+    fn evaluate(text: &str, registers: &Registers) -> AppResult<bool> {
+        match condition(text.as_bytes()) {
+            IResult::Done(&[], parsed) => parsed.evaluate(registers),
+            other => panic!("couldn't parse {}: {:?}", text, other),
+        }
+    }
+
     #[test]
     fn test_program() {
         assert_eq!(
@@ -143,10 +356,8 @@ mod tests {
             IResult::Done(&b""[..], Program{
                     register: "b".into(),
                     operation: Operation::Increment,
-                    value: 5,
-                    condition_register: "a".into(),
-                    condition_op: ConditionOp::Gt,
-                    condition_value: 1,
+                    value: Expr::Value(5),
+                    condition: Condition::Compare(register("a"), ConditionOp::Gt, Expr::Value(1)),
                 }
             )
         );
@@ -159,15 +370,68 @@ mod tests {
             IResult::Done(&b""[..], Program{
                     register: "c".into(),
                     operation: Operation::Decrement,
-                    value: -10,
-                    condition_register: "a".into(),
-                    condition_op: ConditionOp::Gte,
-                    condition_value: 1,
+                    value: Expr::Value(-10),
+                    condition: Condition::Compare(register("a"), ConditionOp::Gte, Expr::Value(1)),
                 }
             )
         );
     }
 
+    #[test]
+    fn test_expr() {
+        assert_eq!(
+            expr(&b"a + 2 * (b - 1) - -c"[..]),
+            IResult::Done(&b""[..], binary(
+                BinaryOp::Sub,
+                binary(
+                    BinaryOp::Add,
+                    register("a"),
+                    binary(BinaryOp::Mul, Expr::Value(2), binary(BinaryOp::Sub, register("b"), Expr::Value(1))),
+                ),
+                Expr::Neg(Box::new(register("c"))),
+            ))
+        );
+
+        let mut registers = Registers::default();
+        registers.set("a", 7);
+        let value = |text: &str| match expr(text.as_bytes()) {
+            IResult::Done(_, e) => e.evaluate(&registers),
+            other => panic!("couldn't parse {}: {:?}", text, other),
+        };
+        assert_eq!(value("a / 2 + a % 4 * 10").unwrap(), 33);
+        assert_eq!(value("-a - 1").unwrap(), -8);
+        assert!(value("a / b").is_err());
+        assert!(value("a * a * a * a * a * a * a * a * a * a * a * a * a * a * a * a * a * a * a * a * a * a * a").is_err());
+    }
+
+    #[test]
+    fn test_condition() {
+        let mut registers = Registers::default();
+        registers.set("a", 1);
+        registers.set("b", 2);
+        assert!(evaluate("a < b", &registers).unwrap());
+        assert!(evaluate("a + 1 == b and not b != 2", &registers).unwrap());
+        assert!(evaluate("a > b or a == 1 and b == 2", &registers).unwrap());
+        assert!(!evaluate("(a > b or a == 1) and b == 3", &registers).unwrap());
+        assert!(evaluate("not (a > b)", &registers).unwrap());
+        // `or` doesn't evaluate its right side once the left is true
+        assert!(evaluate("a == 1 or a / c == 0", &registers).unwrap());
+        assert!(evaluate("a == 0 or a / c == 0", &registers).is_err());
+        // keywords are only keywords where a register can't go
+        registers.set("not", 3);
+        assert!(evaluate("not == 3 and (not < 4)", &registers).unwrap());
+    }
+
+    #[test]
+    fn test_highest() {
+        let mut registers = Registers::default();
+        assert_eq!(registers.largest(), None);
+        registers.set("a", 5);
+        registers.set("a", -1);
+        registers.set("b", -3);
+        assert_eq!((registers.largest(), registers.highest()), (Some(-1), 5));
+    }
+
     #[test]
     fn test_part1() {
         assert_eq!(part1("b inc 5 if a > 1
@@ -183,4 +447,17 @@ a inc 1 if b < 5
 c dec -10 if a >= 1
 c inc -20 if c == 10").unwrap(), 10);
     }
+
+    #[test]
+    fn test_extended() {
+        assert_eq!(part1("b inc a + 3 if a == 0 and b == 0
+a inc b * 2 if b > a or a < 0
+c dec -(a - b) if not (c != 0)").unwrap(), 6);
+        let err = part1("a inc 1 if b == 0\nb inc a / c if a == 1").unwrap_err();
+        assert_eq!(err.to_string(), "line 2: division by zero");
+        // literals are as wide as the registers
+        assert_eq!(part1("a inc 3000000000 if b == 0").unwrap(), 3_000_000_000);
+        assert_eq!(part1("a dec 99999999999 if b == 0\nb inc 1 if a < 0").unwrap(), 1);
+        assert!(part1("a inc 9999999999999999999 if b == 0").is_err());
+    }
 }
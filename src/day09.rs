@@ -1,16 +1,27 @@
+use parsers::ParseError;
 use shared::AppResult;
 // use itertools::Itertools;
 
 pub fn part1(input: &str) -> AppResult<u32> {
+    check_start(input)?;
     parse_group(&mut input.chars(), 1)
 }
 
 
 pub fn part2(input: &str) -> AppResult<u32> {
+    check_start(input)?;
     clean_garbage(&mut input.chars(), true)
 }
 
 
+fn check_start(input: &str) -> AppResult<()> {
+    if !input.starts_with('{') {
+        return Err(ParseError::new(9, 1, 1, input, "`{`").into());
+    }
+    Ok(())
+}
+
+
 fn parse_group(input: &mut impl Iterator<Item=char>, score: u32) -> AppResult<u32> {
     // println!("starting with {}", score);
     let mut count = score;
@@ -118,6 +129,12 @@ mod tests {
         assert_eq!(parse_group(&mut"{{<a!>},{<a!>},{<a!>},{<ab>}}".chars(), 1).unwrap(), 3);
     }
 
+    #[test]
+    fn test_check_start() {
+        assert!(part1("<>{}").is_err());
+        assert!(part2("").is_err());
+    }
+
     #[test]
     fn test_clean_garbage() {
         println!("");
@@ -2,6 +2,7 @@ use std::cmp::max;
 use std::ops;
 use std::str::FromStr;
 use failure::Error;
+use parsers::parse_separated_values;
 use shared::AppResult;
 
 /*
@@ -18,22 +19,22 @@ use shared::AppResult;
 
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
-struct Coor {
-    x: i32,
-    y: i32,
-    z: i32,
+pub struct Coor {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
 }
 
 impl Coor {
-    fn new(x: i32, y: i32, z: i32) -> Self {
+    pub fn new(x: i32, y: i32, z: i32) -> Self {
         Coor{x, y, z}
     }
 
-    fn zero() -> Self {
+    pub fn zero() -> Self {
         Coor::new(0, 0, 0)
     }
 
-    fn distance(&self) -> u32 {
+    pub fn distance(&self) -> u32 {
         max(max(self.x.abs(), self.y.abs()), self.z.abs()) as u32
     }
 }
@@ -75,10 +76,14 @@ impl FromStr for Coor {
 }
 
 
+fn parse(input: &str) -> AppResult<Vec<Coor>> {
+    parse_separated_values(11, 1, input, ',', "a direction (n, ne, se, s, sw or nw)")
+}
+
+
 pub fn part1(input: &str) -> AppResult<u32> {
-    Ok(input
-        .split(',')
-        .filter_map(|c| c.parse::<Coor>().ok())
+    Ok(parse(input)?
+        .into_iter()
         .fold(Coor::zero(), |acc, c| acc + c)
         .distance()
     )
@@ -88,9 +93,7 @@ pub fn part1(input: &str) -> AppResult<u32> {
 pub fn part2(input: &str) -> AppResult<u32> {
     let mut pos = Coor::zero();
     let mut furthest = 0;
-    for coor in input
-        .split(',')
-        .filter_map(|c| c.parse::<Coor>().ok()) {
+    for coor in parse(input)? {
             pos += coor;
             furthest = max(furthest, pos.distance());
         }
@@ -101,6 +104,7 @@ pub fn part2(input: &str) -> AppResult<u32> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use parsers::ParseError;
 
     #[test]
     fn test_part1() {
@@ -117,4 +121,10 @@ mod tests {
         assert_eq!(part2("ne,ne,s,s").unwrap(), 2);
         assert_eq!(part2("se,sw,se,sw,sw").unwrap(), 3);
     }
+
+    #[test]
+    fn test_parse_error() {
+        let err = part1("ne,ne,e").unwrap_err().downcast::<ParseError>().unwrap();
+        assert_eq!(err.column, 7);
+    }
 }
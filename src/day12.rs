@@ -1,7 +1,6 @@
-use parsers::positive_integer;
+use parsers::{parse_lines, positive_integer};
 use shared::AppResult;
 use std::collections::{HashSet, VecDeque};
-use nom::IResult;
 
 #[derive(Debug, PartialEq)]
 struct Pipe {
@@ -37,25 +36,19 @@ fn walk(pipes: &[Pipe], start: u32) -> HashSet<u32> {
     seen
 }
 
-fn parse(input: &str) -> Vec<Pipe> {
-    input
-        .split('\n')
-        .filter_map(|row| match pipe(row.as_bytes()) {
-            IResult::Done(_, pipe) => Some(pipe),
-            _ => None,
-        })
-        .collect::<Vec<Pipe>>()
+fn parse(input: &str) -> AppResult<Vec<Pipe>> {
+    parse_lines(12, input, "`n <-> n, n, ...`", pipe)
 }
 
 pub fn part1(input: &str) -> AppResult<u32> {
-    let pipes = parse(input);
+    let pipes = parse(input)?;
     Ok(walk(&pipes, 0).len() as u32)
 }
 
 
 pub fn part2(input: &str) -> AppResult<u32> {
     let mut groups = 0;
-    let pipes = parse(input);
+    let pipes = parse(input)?;
     let mut seen: HashSet<u32> = HashSet::new();
     for pipe in pipes.iter() {
         if seen.contains(&pipe.from) {
@@ -88,6 +81,11 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_error() {
+        assert!(parse("0 <-> 2\n1 <- 1").is_err());
+    }
+
     #[test]
     fn test_part1() {
         assert_eq!(part1("0 <-> 0").unwrap(), 1);
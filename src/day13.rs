@@ -1,5 +1,4 @@
-use nom::IResult;
-use parsers::positive_integer;
+use parsers::{parse_lines, positive_integer};
 use shared::AppResult;
 
 
@@ -12,13 +11,8 @@ named!(layer <(u32, u32)>,
     )
 );
 
-fn parse(input: &str) -> Vec<(u32, u32)> {
-    input.split('\n')
-        .filter_map(|row| match layer(row.as_bytes()) {
-            IResult::Done(_, tuple) => Some(tuple),
-            _ => None,
-        })
-        .collect()
+fn parse(input: &str) -> AppResult<Vec<(u32, u32)>> {
+    parse_lines(13, input, "`depth: range`", layer)
 }
 
 
@@ -37,12 +31,12 @@ fn hit(layers: &[(u32, u32)], delay: u32) -> bool {
 }
 
 pub fn part1(input: &str) -> AppResult<u32> {
-    Ok(send(&parse(input), 0))
+    Ok(send(&parse(input)?, 0))
 }
 
 
 pub fn part2(input: &str) -> AppResult<u32> {
-    let layers = parse(input);
+    let layers = parse(input)?;
     (0..)
     .skip_while(|&delay| hit(&layers, delay))
     .next()
@@ -59,7 +53,8 @@ mod tests {
     fn test_parse() {
         assert_eq!(parse("0: 3
 1: 2
-4: 4"), vec![(0, 3), (1, 2), (4, 4)]);
+4: 4").unwrap(), vec![(0, 3), (1, 2), (4, 4)]);
+        assert!(parse("0: 3\n1 2").is_err());
     }
 
     #[test]
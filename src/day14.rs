@@ -1,128 +1,327 @@
+use std::thread;
+
+use knot;
+use parsers::ParseError;
 use shared::AppResult;
-use std::collections::{HashSet, VecDeque};
-use day10::sparse;
-use position::Position;
+use solution::Options;
+
+
+pub const SIZE: usize = 128;
+
+
+// the disk's used squares, one bit per square. column 0 is each row's most
+// significant bit, matching the order of the knot hash's bits
+#[derive(Debug, PartialEq, Clone)]
+pub struct Grid {
+    rows: Vec<u128>,
+}
+
+impl Grid {
+    // row `y` is the knot hash of `key-y`
+    pub fn new(key: &str) -> Self {
+        Grid::with_threads(key, default_threads())
+    }
+
+    // hashes the rows in contiguous blocks, one per thread. each row only
+    // depends on its index, so the grid is the same for any thread count
+    pub fn with_threads(key: &str, threads: usize) -> Self {
+        let block = SIZE.div_ceil(threads.max(1));
+        let mut rows = vec![0; SIZE];
+        thread::scope(|scope| {
+            for (index, rows) in rows.chunks_mut(block).enumerate() {
+                scope.spawn(move || {
+                    for (offset, row) in rows.iter_mut().enumerate() {
+                        *row = hash_row(key, index * block + offset);
+                    }
+                });
+            }
+        });
+        Grid { rows }
+    }
+
+    // rows of `#` (used) and `.` (free), e.g. from the puzzle's example
+    pub fn from_ascii(text: &str) -> AppResult<Self> {
+        let mut rows = vec![0; SIZE];
+        for (y, line) in text.lines().enumerate() {
+            if y >= SIZE {
+                return Err(ParseError::new(14, y + 1, 1, line, &format!("at most {} rows", SIZE)).into());
+            }
+            for (x, c) in line.chars().enumerate() {
+                if x >= SIZE {
+                    let expected = format!("at most {} columns", SIZE);
+                    return Err(ParseError::new(14, y + 1, x + 1, line, &expected).into());
+                }
+                match c {
+                    '#' => rows[y] |= 1 << (SIZE - 1 - x),
+                    '.' => {},
+                    _ => return Err(ParseError::new(14, y + 1, x + 1, line, "`#` or `.`").into()),
+                }
+            }
+        }
+        Ok(Grid { rows })
+    }
+
+    pub fn is_used(&self, x: usize, y: usize) -> bool {
+        self.rows[y] & (1 << (SIZE - 1 - x)) != 0
+    }
+
+    pub fn used(&self) -> u32 {
+        self.rows.iter().map(|row| row.count_ones()).sum()
+    }
 
+    pub fn regions(&self) -> Regions {
+        let mut sets = UnionFind::new(SIZE * SIZE);
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                if !self.is_used(x, y) {
+                    continue;
+                }
+                if x + 1 < SIZE && self.is_used(x + 1, y) {
+                    sets.union(y * SIZE + x, y * SIZE + x + 1);
+                }
+                if y + 1 < SIZE && self.is_used(x, y + 1) {
+                    sets.union(y * SIZE + x, (y + 1) * SIZE + x);
+                }
+            }
+        }
 
-// fn dense(bytes: Vec<usize>) -> Vec<usize> {
-//     bytes.chunks(16).map(
-//         |chunk| chunk.iter().fold(0, |x, y| x ^ y)
-//     ).collect()
-// }
+        // number the regions in reading order of their first square
+        let mut labels = vec![None; SIZE * SIZE];
+        let mut root_labels = vec![None; SIZE * SIZE];
+        let mut sizes = vec![];
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                if !self.is_used(x, y) {
+                    continue;
+                }
+                let root = sets.find(y * SIZE + x);
+                let label = match root_labels[root] {
+                    Some(label) => label,
+                    None => {
+                        root_labels[root] = Some(sizes.len());
+                        sizes.push(0);
+                        sizes.len() - 1
+                    },
+                };
+                sizes[label] += 1;
+                labels[y * SIZE + x] = Some(label);
+            }
+        }
+        Regions { labels, sizes }
+    }
+}
 
-fn dense_binary(bytes: Vec<usize>) -> String {
-    bytes.chunks(16).map(
-        |chunk| format!("{:08b}", chunk.iter().fold(0, |x, y| x ^ y))
-    ).collect()
+fn hash_row(key: &str, y: usize) -> u128 {
+    let digest = knot::hash(format!("{}-{}", key, y).as_bytes());
+    digest.iter().fold(0, |row, &byte| row << 8 | byte as u128)
 }
 
-fn dense_binary_count(bytes: Vec<usize>) -> u32 {
-    bytes.chunks(16).map(
-        |chunk| chunk.iter().fold(0, |x, y| x ^ y).count_ones()
-    ).sum()
+pub fn default_threads() -> usize {
+    thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
 }
 
 
-pub fn part1(input: &str) -> AppResult<u32> {
-    Ok(
-        (0..128).map(
-        |suffix| dense_binary_count(sparse(&format!("{}-{}", input, suffix)))
-        ).sum())
+struct UnionFind {
+    parents: Vec<usize>,
+    sizes: Vec<usize>,
 }
 
-fn get_coors(input: &str) -> Vec<Position> {
-    let mut coors = vec![];
-    for suffix in 0..128 {
-        let mut row_coors: Vec<_> = dense_binary(sparse(
-            &format!("{}-{}", input, suffix)
-        ))
-            .chars()
-            .enumerate()
-            .filter(|&(_, x)| x == '1')
-            .map(|(i, _)| Position::new(i as i32, suffix))
-            .collect();
-        coors.append(&mut row_coors);
+impl UnionFind {
+    fn new(count: usize) -> Self {
+        UnionFind {
+            parents: (0..count).collect(),
+            sizes: vec![1; count],
+        }
+    }
+
+    // with path halving, so later finds are shorter
+    fn find(&mut self, mut node: usize) -> usize {
+        while self.parents[node] != node {
+            self.parents[node] = self.parents[self.parents[node]];
+            node = self.parents[node];
+        }
+        node
+    }
+
+    // the smaller set joins the larger
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        let (small, large) = if self.sizes[a] < self.sizes[b] { (a, b) } else { (b, a) };
+        self.parents[small] = large;
+        self.sizes[large] += self.sizes[small];
     }
-    coors
 }
 
-fn count_regions(coors: Vec<Position>) -> usize {
-    let mut sum = 0;
-    let mut seen = HashSet::new();
-    let all_coors: HashSet<_> = coors.iter().collect();
-    // println!("{:?}", &all_coors);
-    for coor in coors.clone() {
-        if seen.contains(&coor) {
-            continue;
+
+// the connected regions of used squares, labelled from 0 in the order their
+// first square appears, reading left to right and top to bottom
+#[derive(Debug, PartialEq, Clone)]
+pub struct Regions {
+    labels: Vec<Option<usize>>,
+    sizes: Vec<usize>,
+}
+
+impl Regions {
+    pub fn count(&self) -> usize {
+        self.sizes.len()
+    }
+
+    pub fn label(&self, x: usize, y: usize) -> Option<usize> {
+        self.labels[y * SIZE + x]
+    }
+
+    // the number of squares in each region, by label
+    pub fn sizes(&self) -> &[usize] {
+        &self.sizes
+    }
+
+    // one character per square: `.` if free, else the label's last digit in
+    // base 62 (0-9, a-z, A-Z)
+    pub fn ascii(&self) -> String {
+        const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+        let mut out = String::with_capacity(SIZE * (SIZE + 1));
+        for cells in self.labels.chunks(SIZE) {
+            out.extend(cells.iter().map(|label| match *label {
+                Some(label) => DIGITS[label % DIGITS.len()] as char,
+                None => '.',
+            }));
+            out.push('\n');
         }
-        sum += 1;
-        let mut region = VecDeque::new();
-        // println!("new: {:?}", &coor);
-        region.push_back(coor);
-        while let Some(member) = region.pop_front() {
-            if seen.contains(&member) {
-                continue;
-            }
-            // println!("   reg: {:?}", &member);
-            seen.insert(member.clone());
-            for neighbour in member.straight_neighbours() {
-                // println!("      nei?: {:?}", &neighbour);
-                if all_coors.contains(&neighbour) {
-                    // println!("      nei: {:?}", &neighbour);
-                    region.push_back(neighbour);
-                }
-            }
+        out
+    }
+
+    // a plain (P3) PPM image, one pixel per square: free squares are black
+    // and each region gets a colour derived from its label
+    pub fn ppm(&self) -> String {
+        let mut out = format!("P3\n{0} {0}\n255\n", SIZE);
+        for cells in self.labels.chunks(SIZE) {
+            let pixels: Vec<_> = cells.iter()
+                .map(|label| {
+                    let (r, g, b) = label.map_or((0, 0, 0), colour);
+                    format!("{} {} {}", r, g, b)
+                })
+                .collect();
+            out.push_str(&pixels.join(" "));
+            out.push('\n');
         }
+        out
     }
-    sum
+}
+
+// scatters consecutive labels across the colour space, keeping every
+// channel bright enough to stand out from the black background
+fn colour(label: usize) -> (u8, u8, u8) {
+    let hash = (label as u32 + 1).wrapping_mul(2_654_435_761);
+    let channel = |shift: u32| 64 + ((hash >> shift) & 0xff) as u8 % 192;
+    (channel(0), channel(8), channel(16))
+}
+
+
+pub fn part1(input: &str) -> AppResult<u32> {
+    part1_with(input, &Options::default())
 }
 
 
 pub fn part2(input: &str) -> AppResult<u32> {
-    Ok(count_regions(get_coors(input)) as u32)
+    part2_with(input, &Options::default())
 }
 
-/*
-11010100
-01010101
-00001010
-10101101
-01101000
-11001001
-01000100
-11010110
-*/
+
+pub fn part1_with(input: &str, options: &Options) -> AppResult<u32> {
+    Ok(grid(input, options).used())
+}
+
+
+pub fn part2_with(input: &str, options: &Options) -> AppResult<u32> {
+    Ok(grid(input, options).regions().count() as u32)
+}
+
+fn grid(input: &str, options: &Options) -> Grid {
+    Grid::with_threads(input, options.threads.unwrap_or_else(default_threads))
+}
 
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const EXAMPLE: &str = "##.#.#..
+.#.#.#.#
+....#.#.
+#.#.##.#
+.##.#...
+##..#..#
+.#...#..
+##.#.##.";
+
     #[test]
     fn test_part1() {
         assert_eq!(part1("flqrgnkx").unwrap(), 8108);
     }
 
-    #[test] #[ignore]
-    fn test_get_coors() {
-        let coors = get_coors("flqrgnkx");
-        assert_eq!(
-            coors.into_iter().filter(|p| p.x < 4 && p.y < 4).collect::<Vec<_>>(),
-            [(0, 0), (1, 0), (3, 0), (1, 1), (3, 1), (0, 3), (2, 3)]
-            .iter().map(|&(x, y)| Position::new(x, y)).collect::<Vec<_>>()
-        );
+    #[test]
+    fn test_grid() {
+        let grid = Grid::new("flqrgnkx");
+        let corner: String = (0..8)
+            .map(|y| (0..8).map(|x| if grid.is_used(x, y) { '#' } else { '.' }).collect::<String>() + "\n")
+            .collect();
+        assert_eq!(corner, format!("{}\n", EXAMPLE));
+        let err = Grid::from_ascii("##\n#x").unwrap_err().downcast::<ParseError>().unwrap();
+        assert_eq!(err, ParseError::new(14, 2, 2, "#x", "`#` or `.`"));
+        assert!(Grid::from_ascii(&".".repeat(SIZE + 1)).is_err());
+    }
+
+    #[test]
+    fn test_threads() {
+        let sequential = Grid::with_threads("flqrgnkx", 1);
+        for &threads in &[0, 2, 3, 7, 128, 200] {
+            assert_eq!(Grid::with_threads("flqrgnkx", threads), sequential, "{} threads", threads);
+        }
+    }
+
+    #[test]
+    fn test_regions() {
+        let regions = Grid::from_ascii(EXAMPLE).unwrap().regions();
+        assert_eq!(regions.count(), 12);
+        assert_eq!(regions.label(0, 0), Some(0));
+        assert_eq!(regions.label(1, 1), Some(0));
+        assert_eq!(regions.label(2, 0), None);
+        assert_eq!(regions.label(3, 0), regions.label(3, 1));
+        assert_eq!(regions.sizes().iter().sum::<usize>(), 29);
+        assert_eq!(regions.sizes()[0], 3);
+        let ascii = regions.ascii();
+        let corner: Vec<_> = ascii.lines().take(3).map(|line| &line[..8]).collect();
+        assert_eq!(corner, vec!["00.1.2..", ".0.1.2.3", "....4.5."]);
+        assert_eq!(ascii.lines().nth(7).map(str::len), Some(SIZE));
     }
 
     #[test]
-    fn test_count_regions() {
-        assert_eq!(count_regions(
-            [(0, 0), (1, 0), (3, 0), (1, 1), (3, 1), (0, 3), (2, 3)]
-            .iter().map(|&(x, y)| Position::new(x, y)).collect::<Vec<_>>()),
-        4);
+    fn test_ppm() {
+        let regions = Grid::from_ascii("#.").unwrap().regions();
+        let ppm = regions.ppm();
+        let mut lines = ppm.lines();
+        assert_eq!(lines.next(), Some("P3"));
+        assert_eq!(lines.next(), Some("128 128"));
+        assert_eq!(lines.next(), Some("255"));
+        let (r, g, b) = colour(0);
+        assert!(lines.next().unwrap().starts_with(&format!("{} {} {} 0 0 0 ", r, g, b)));
+        assert_eq!(ppm.lines().count(), 3 + SIZE);
     }
 
-    #[test] #[ignore]
+    #[test]
     fn test_part2() {
         assert_eq!(part2("flqrgnkx").unwrap(), 1242);
     }
+
+    #[test]
+    fn test_parts_with_threads() {
+        for &threads in &[1, 5] {
+            let options = Options { threads: Some(threads) };
+            assert_eq!(part1_with("flqrgnkx", &options).unwrap(), 8108, "{} threads", threads);
+            assert_eq!(part2_with("flqrgnkx", &options).unwrap(), 1242, "{} threads", threads);
+        }
+    }
 }
@@ -1,3 +1,5 @@
+use nom::alpha;
+use parsers::{parse_lines, positive_integer};
 use shared::AppResult;
 
 const LOWER16: u64 = 0b1111_1111_1111_1111;
@@ -43,13 +45,32 @@ fn do_rounds_v2(a: u64, b: u64) -> u32 {
     matches
 }
 
-pub fn part1(_input: &str) -> AppResult<u32> {
-    Ok(do_rounds(722, 354))
+named!(generator <u64>,
+    do_parse!(
+        tag!("Generator ") >>
+        alpha >>
+        tag!(" starts with ") >>
+        start: positive_integer >>
+        (start as u64)
+    )
+);
+
+fn parse(input: &str) -> AppResult<(u64, u64)> {
+    match parse_lines(15, input, "`Generator X starts with N`", generator)?[..] {
+        [a, b] => Ok((a, b)),
+        ref starts => bail!("expected two generators, found {}", starts.len()),
+    }
+}
+
+pub fn part1(input: &str) -> AppResult<u32> {
+    let (a, b) = parse(input)?;
+    Ok(do_rounds(a, b))
 }
 
 
-pub fn part2(_input: &str) -> AppResult<u32> {
-    Ok(do_rounds_v2(722, 354))
+pub fn part2(input: &str) -> AppResult<u32> {
+    let (a, b) = parse(input)?;
+    Ok(do_rounds_v2(a, b))
 }
 
 
@@ -57,6 +78,14 @@ pub fn part2(_input: &str) -> AppResult<u32> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("Generator A starts with 65
+Generator B starts with 8921").unwrap(), (65, 8921));
+        assert!(parse("Generator A starts with 65").is_err());
+        assert!(parse("Generator A starts at 65\nGenerator B starts with 8921").is_err());
+    }
+
     #[test] #[ignore]
     fn test_do_rounds() {
         assert_eq!(do_rounds(65, 8921), 588);
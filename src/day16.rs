@@ -1,10 +1,13 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use cycle;
 use shared::AppResult;
-use parsers::positive_integer;
-use nom::{IResult, alpha};
+use parsers::{parse_separated, positive_integer};
 
 
-#[derive(Debug, PartialEq)]
-enum Move {
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Move {
     Spin(usize),
     Exchange(usize, usize),
     Partner(char, char),
@@ -28,13 +31,18 @@ named!(exchange <Move>,
     )
 );
 
+// a single lower case letter
+named!(program <char>,
+    one_of!("abcdefghijklmnopqrstuvwxyz")
+);
+
 named!(partner <Move>,
     do_parse!(
         tag!("p") >>
-        x: alpha >>
+        x: program >>
         tag!("/") >>
-        y: alpha >>
-        (Move::Partner(x[0] as char, y[0] as char))
+        y: program >>
+        (Move::Partner(x, y))
     )
 );
 
@@ -43,100 +51,237 @@ named!(dance_move <Move>,
 );
 
 
-fn parse(input: &str) -> Vec<Move> {
-    input.split(',')
-        .filter_map(|row| match dance_move(row.as_bytes()) {
-            IResult::Done(_, mv) => Some(mv),
-            o => panic!("parsing failed {:?}", o),
-        })
-        .collect()
+pub fn parse(input: &str) -> AppResult<Vec<Move>> {
+    parse_separated(16, 1, input, ',', "a dance move (sN, xA/B or pA/B)", dance_move)
 }
 
-fn do_spin(mut line: Vec<char>, step: usize) -> Vec<char> {
-    let len = line.len();
-    let mut end = line.split_off(len - step);
-    end.append(&mut line);
-    end
+// checks every move fits a line of `size` programs named from `a`
+pub fn validate(moves: &[Move], size: usize) -> AppResult<()> {
+    let name = |program: char| (program as usize).wrapping_sub('a' as usize) < size;
+    for &mv in moves {
+        let fits = match mv {
+            Move::Spin(n) => n <= size,
+            Move::Exchange(a, b) => a < size && b < size,
+            Move::Partner(a, b) => name(a) && name(b),
+        };
+        if !fits {
+            bail!("{:?} doesn't fit a line of {}", mv, size);
+        }
+    }
+    Ok(())
 }
 
-fn do_exchange(mut line: Vec<char>, x: usize, y: usize) -> Vec<char> {
-    line.swap(x, y);
-    line
-}
 
-fn do_partner(line: Vec<char>, x: char, y: char) -> Vec<char> {
-    let a = line.iter().position(|&c| c == x).expect("x not found");
-    let b = line.iter().position(|&c| c == y).expect("y not found");
-    do_exchange(line, a, b)
+// a line that dances in place. the programs sit in a ring buffer whose
+// start moves on a spin, and each program's slot is indexed by name, so
+// every move takes constant time
+#[derive(Debug, Clone)]
+pub struct Line {
+    // names as indices from `a`
+    ring: Vec<usize>,
+    start: usize,
+    slots: Vec<usize>,
 }
 
+impl Line {
+    // `programs` must be the first letters of the alphabet, in any order
+    pub fn new(programs: &str) -> AppResult<Self> {
+        let size = programs.len();
+        let mut slots = vec![size; size];
+        let mut ring = vec![];
+        for (slot, c) in programs.chars().enumerate() {
+            let name = (c as usize).wrapping_sub('a' as usize);
+            if name >= size || slots[name] != size {
+                bail!("`{}` isn't a line of distinct programs from `a`", programs);
+            }
+            slots[name] = slot;
+            ring.push(name);
+        }
+        Ok(Line { ring, start: 0, slots })
+    }
+
+    fn swap_slots(&mut self, x: usize, y: usize) {
+        self.ring.swap(x, y);
+        self.slots[self.ring[x]] = x;
+        self.slots[self.ring[y]] = y;
+    }
 
-fn run(input: &str, programs: &str) -> String {
-    let mut programs: Vec<_> = programs.chars().collect();
-    // println!("{:?}", parse(input));
-    for mv in parse(input) {
+    // the move must fit the line; see `validate`
+    pub fn perform(&mut self, mv: Move) {
+        let size = self.ring.len();
         match mv {
-            // Move::Spin(n) => {},
-            // Move::Exchange(a, b) => {},
-            // Move::Partner(a, b) => {}
-            Move::Spin(n) => {programs = do_spin(programs, n)},
-            Move::Exchange(a, b) => {programs = do_exchange(programs, a, b)},
-            Move::Partner(a, b) => {programs = do_partner(programs, a, b)},
+            Move::Spin(n) => self.start = (self.start + size - n) % size,
+            Move::Exchange(a, b) => {
+                let (x, y) = ((self.start + a) % size, (self.start + b) % size);
+                self.swap_slots(x, y);
+            },
+            Move::Partner(a, b) => {
+                let (x, y) = (self.slots[a as usize - 'a' as usize], self.slots[b as usize - 'a' as usize]);
+                self.swap_slots(x, y);
+            },
+        }
+    }
+
+    pub fn dance(&mut self, moves: &[Move]) {
+        for &mv in moves {
+            self.perform(mv);
         }
-        // println!("{}", programs.iter().collect::<String>());
     }
-    programs.iter().collect()
+
+    // the names in line order
+    fn order<'a>(&'a self) -> impl Iterator<Item=usize> + 'a {
+        let size = self.ring.len();
+        (0..size).map(move |i| self.ring[(self.start + i) % size])
+    }
 }
 
-pub fn part1(input: &str) -> AppResult<u32> {
-    println!("{}", run(input, "abcdefghijklmnop"));
-    Ok(0)
+impl PartialEq for Line {
+    fn eq(&self, other: &Line) -> bool {
+        self.order().eq(other.order())
+    }
 }
 
-/*
+impl Eq for Line {}
 
-abcde
-eabdc
+impl Hash for Line {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for name in self.order() {
+            name.hash(state);
+        }
+    }
+}
 
-(a e c b)^2 = (a c)(b e)
-abcde
-ceadb
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for name in self.order() {
+            write!(f, "{}", (name as u8 + b'a') as char)?;
+        }
+        Ok(())
+    }
+}
 
-perm:
 
-abcdefghijklmnop
-mkangclhfjodpbei
+pub fn run(input: &str, programs: &str) -> AppResult<String> {
+    let moves = parse(input)?;
+    let mut line = Line::new(programs)?;
+    validate(&moves, programs.len())?;
+    line.dance(&moves);
+    Ok(line.to_string())
+}
 
-6, 8
-1b % 6 == 4
-(a m p i f c)(b k o e g l d n)
+// dances `times` times by simulation, skipping whole cycles of line-ups
+pub fn run_times(input: &str, programs: &str, times: u64) -> AppResult<String> {
+    let moves = parse(input)?;
+    validate(&moves, programs.len())?;
+    let line = cycle::state_after(&Line::new(programs)?, times, |line| {
+        let mut line = line.clone();
+        line.dance(&moves);
+        line
+    });
+    Ok(line.to_string())
+}
 
-(a m p i f c)^4 = (a f p)(m c i)
+pub fn part1(input: &str) -> AppResult<String> {
+    run(input, "abcdefghijklmnop")
+}
 
+// `apply` moves the item at `map[i]` to `i`
+#[derive(Debug, PartialEq, Clone)]
+pub struct Permutation {
+    map: Vec<usize>,
+}
 
-abcdefghijklmnop
-fbidepghmjklcnoa
+impl Permutation {
+    pub fn identity(size: usize) -> Self {
+        Permutation { map: (0..size).collect() }
+    }
+
+    pub fn apply<T: Clone>(&self, items: &[T]) -> Vec<T> {
+        self.map.iter().map(|&i| items[i].clone()).collect()
+    }
 
+    // applying the result is applying `self`, then `other`
+    pub fn then(&self, other: &Permutation) -> Self {
+        Permutation { map: other.apply(&self.map) }
+    }
 
-name:
+    // `self` applied `n` times, by repeated squaring
+    pub fn pow(&self, mut n: u64) -> Self {
+        let mut result = Permutation::identity(self.map.len());
+        let mut square = self.clone();
+        while n > 0 {
+            if n & 1 == 1 {
+                result = result.then(&square);
+            }
+            square = square.then(&square);
+            n >>= 1;
+        }
+        result
+    }
+}
+
+
+// spins and exchanges move programs by position, and partners swap them by
+// name, so the two kinds of move commute: a dance is one permutation of
+// positions followed by one of names
+#[derive(Debug, PartialEq, Clone)]
+pub struct Dance {
+    positions: Permutation,
+    // the name each name becomes, as indices from `a`
+    names: Permutation,
+}
+
+impl Dance {
+    pub fn new(moves: &[Move], size: usize) -> AppResult<Self> {
+        validate(moves, size)?;
+        let mut positions: Vec<_> = (0..size).collect();
+        let mut names: Vec<_> = (0..size).collect();
+        for &mv in moves {
+            match mv {
+                Move::Spin(n) => positions.rotate_right(n),
+                Move::Exchange(a, b) => positions.swap(a, b),
+                Move::Partner(a, b) => {
+                    let (a, b) = (a as usize - 'a' as usize, b as usize - 'a' as usize);
+                    let x = names.iter().position(|&n| n == a).expect("every name is present");
+                    let y = names.iter().position(|&n| n == b).expect("every name is present");
+                    names.swap(x, y);
+                },
+            }
+        }
+        Ok(Dance {
+            positions: Permutation { map: positions },
+            names: Permutation { map: names },
+        })
+    }
 
-abcdefghijklmnop
-eojicpagblmnhfkd
+    pub fn repeat(&self, times: u64) -> Self {
+        Dance {
+            positions: self.positions.pow(times),
+            names: self.names.pow(times),
+        }
+    }
 
-16
-1b % 16 == 0
-(a e c j l m f p d i b o k m h g)
+    // `programs` are the first letters of the alphabet, in any order
+    pub fn perform(&self, programs: &str) -> String {
+        let line: Vec<_> = programs.bytes().map(|c| (c - b'a') as usize).collect();
+        self.positions.apply(&line)
+            .into_iter()
+            .map(|name| (self.names.map[name] as u8 + b'a') as char)
+            .collect()
+    }
+}
 
-*/
 
-pub fn part2(_input: &str) -> AppResult<u32> {
-    Ok(0)
+pub fn part2(input: &str) -> AppResult<String> {
+    let programs = "abcdefghijklmnop";
+    Ok(Dance::new(&parse(input)?, programs.len())?.repeat(1_000_000_000).perform(programs))
 }
 
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use nom::IResult;
 
     #[test]
     fn test_spin() {
@@ -168,33 +313,64 @@ mod tests {
     #[test]
     fn test_parse() {
         assert_eq!(
-            parse("s1,x3/4,pe/b"), vec![Move::Spin(1), Move::Exchange(3, 4), Move::Partner('e', 'b')]
+            parse("s1,x3/4,pe/b").unwrap(),
+            vec![Move::Spin(1), Move::Exchange(3, 4), Move::Partner('e', 'b')]
         );
+        assert!(parse("s1,x3-4").is_err());
+        assert!(parse("pab/c").is_err());
+        assert!(parse("pa/bc").is_err());
     }
 
     #[test]
-    fn test_do_spin() {
-        let line = "abcde";
-        assert_eq!(do_spin(line.chars().collect(), 1), "eabcd".chars().collect::<Vec<_>>());
+    fn test_line() {
+        let mut line = Line::new("abcde").unwrap();
+        line.perform(Move::Spin(1));
+        assert_eq!(line.to_string(), "eabcd");
+        line.perform(Move::Spin(5));
+        assert_eq!(line.to_string(), "eabcd");
+        line.perform(Move::Exchange(1, 2));
+        assert_eq!(line.to_string(), "ebacd");
+        line.perform(Move::Partner('e', 'd'));
+        assert_eq!(line.to_string(), "dbace");
+        // lines are equal if their programs are in the same order, wherever
+        // the ring starts
+        assert_eq!(line, Line::new("dbace").unwrap());
+        assert!(line != Line::new("abcde").unwrap());
+        assert!(Line::new("abb").is_err());
+        assert!(Line::new("abd").is_err());
     }
 
     #[test]
-    fn test_do_exchange() {
-        let line = "abcde";
-        assert_eq!(do_exchange(line.chars().collect(), 1, 2), "acbde".chars().collect::<Vec<_>>());
+    fn test_run() {
+        assert_eq!(
+            run("s1,x3/4,pe/b", "abcde").unwrap(), "baedc"
+        );
+        assert!(run("s6", "abcde").is_err());
     }
 
     #[test]
-    fn test_do_partner() {
-        let line = "abcde";
-        assert_eq!(do_partner(line.chars().collect(), 'b', 'c'), "acbde".chars().collect::<Vec<_>>());
+    fn test_pow() {
+        let cycle = Permutation { map: vec![1, 2, 0, 4, 3] };
+        assert_eq!(cycle.pow(0), Permutation::identity(5));
+        assert_eq!(cycle.pow(2), cycle.then(&cycle));
+        assert_eq!(cycle.pow(6), Permutation::identity(5));
+        assert_eq!(cycle.pow(1_000_000_003), cycle);
     }
 
     #[test]
-    fn test_run() {
-        assert_eq!(
-            run("s1,x3/4,pe/b", "abcde"), "baedc"
-        );
+    fn test_dance() {
+        let dance = Dance::new(&parse("s1,x3/4,pe/b").unwrap(), 5).unwrap();
+        assert_eq!(dance.perform("abcde"), "baedc");
+        assert_eq!(dance.repeat(2).perform("abcde"), "ceadb");
+        let moves = "s3,pa/c,x0/4,pb/e,s1,x2/3,pd/a";
+        let mut programs = "abcde".to_string();
+        let dance = Dance::new(&parse(moves).unwrap(), 5).unwrap();
+        for times in 1..30 {
+            programs = run(moves, &programs).unwrap();
+            assert_eq!(dance.repeat(times).perform("abcde"), programs, "{} dances", times);
+        }
+        assert_eq!(run_times(moves, "abcde", 1_000_000_000).unwrap(), dance.repeat(1_000_000_000).perform("abcde"));
+        assert!(Dance::new(&parse("x1/5").unwrap(), 5).is_err());
+        assert!(Dance::new(&parse("pa/f").unwrap(), 5).is_err());
     }
-
 }
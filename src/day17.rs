@@ -1,35 +1,117 @@
+use parsers::parse_value;
 use shared::AppResult;
 
+
+const PART1_INSERTIONS: u32 = 2017;
+const PART2_INSERTIONS: u32 = 50_000_000;
+
+
 pub fn part1(input: &str) -> AppResult<u32> {
-    let inc: usize = input.parse().expect("parsing failed");
-    let mut buf = vec![0];
-    let mut length = 1;
-    let mut pos = 0;
-    for step in 1..2018 {
-    // for step in 1..10 {
-        pos = (pos + inc) % length;
-        buf.insert(pos + 1, step);
-        length += 1;
-        pos += 1; // just inserted so never needs to wrap
-    }
-    Ok(buf[(pos + 1) % length])
+    let step = parse_value(17, 1, input, "a step count")?;
+    let spinlock = Spinlock::run(step, PART1_INSERTIONS);
+    Ok(spinlock.value_after(spinlock.current()).expect("the current value is in the buffer"))
 }
 
 
 pub fn part2(input: &str) -> AppResult<u32> {
-    let inc: usize = input.parse().expect("parsing failed");
-    let mut after0 = 0;
-    let mut length = 1;
-    let mut pos = 0;
-    for step in 1..50_000_000 {
-        pos = (pos + inc) % length;
+    let step = parse_value(17, 1, input, "a step count")?;
+    Ok(value_after_zero(step, PART2_INSERTIONS))
+}
+
+
+// the circular buffer as a linked array: `next[v]` is the value after `v`.
+// each insertion walks `step` links, so it suits moderate insertion counts
+#[derive(Debug, PartialEq, Clone)]
+pub struct Spinlock {
+    step: usize,
+    next: Vec<u32>,
+    current: u32,
+}
+
+impl Spinlock {
+    // a buffer holding just 0
+    pub fn new(step: usize) -> Self {
+        Spinlock { step, next: vec![0], current: 0 }
+    }
+
+    pub fn run(step: usize, insertions: u32) -> Self {
+        let mut spinlock = Spinlock::new(step);
+        for _ in 0..insertions {
+            spinlock.insert();
+        }
+        spinlock
+    }
+
+    // steps forward, then inserts the next value after the current one and
+    // makes it current
+    pub fn insert(&mut self) {
+        let value = self.next.len() as u32;
+        let mut pos = self.current;
+        for _ in 0..self.step % self.next.len() {
+            pos = self.next[pos as usize];
+        }
+        self.next.push(self.next[pos as usize]);
+        self.next[pos as usize] = value;
+        self.current = value;
+    }
+
+    // the value inserted last
+    pub fn current(&self) -> u32 {
+        self.current
+    }
+
+    pub fn value_after(&self, value: u32) -> Option<u32> {
+        self.next.get(value as usize).cloned()
+    }
+}
+
+
+// 0 never moves from the start of the buffer, so the value after it is the
+// last one inserted at index 1. only the position and length are tracked,
+// and runs of insertions that don't wrap round the end are skipped at once
+pub fn value_after_zero(step: usize, insertions: u32) -> u32 {
+    if insertions == 0 {
+        return 0;
+    }
+    // 1 always goes after 0, and from then on the position is never 0
+    let (mut after0, mut pos, mut length): (u32, usize, usize) = (1, 1, 2);
+    let mut value = 2;
+    while value <= insertions {
+        // while `length - pos > step` the next insertion doesn't wrap, and
+        // moves the position `step + 1` further on
+        let remaining = (insertions - value + 1) as usize;
+        // (with a step of 0 nothing ever wraps)
+        let skip = (length - pos - 1).checked_div(step).map_or(remaining, |skip| skip.min(remaining));
+        pos += skip * (step + 1);
+        length += skip;
+        value += skip as u32;
+        if value > insertions {
+            break;
+        }
+
+        pos = (pos + step) % length;
         if pos == 0 {
-            after0 = step;
+            after0 = value;
         }
-        length += 1;
         pos += 1;
+        length += 1;
+        value += 1;
+    }
+    after0
+}
+
+
+// the value after `value` once `insertions` values have been inserted, or
+// None if it was never inserted. the value after 0 is found without
+// building the buffer, so `insertions` can be huge
+pub fn value_after(step: usize, insertions: u32, value: u32) -> Option<u32> {
+    if value > insertions {
+        None
+    } else if value == 0 {
+        Some(value_after_zero(step, insertions))
+    } else {
+        Spinlock::run(step, insertions).value_after(value)
     }
-    Ok(after0)
 }
 
 
@@ -37,8 +119,39 @@ pub fn part2(input: &str) -> AppResult<u32> {
 mod tests {
     use super::*;
 
-    #[test] #[ignore]
+    #[test]
     fn test_part1() {
         assert_eq!(part1("3").unwrap(), 638);
     }
+
+    #[test]
+    fn test_spinlock() {
+        // the puzzle's example after 9 insertions: 0 (9) 5 7 2 4 3 8 6 1
+        let spinlock = Spinlock::run(3, 9);
+        assert_eq!(spinlock.current(), 9);
+        let mut buffer = vec![0];
+        while buffer.len() < 10 {
+            let last = *buffer.last().unwrap();
+            buffer.push(spinlock.value_after(last).unwrap());
+        }
+        assert_eq!(buffer, vec![0, 9, 5, 7, 2, 4, 3, 8, 6, 1]);
+        assert_eq!(spinlock.value_after(1), Some(0));
+        assert_eq!(spinlock.value_after(10), None);
+        assert_eq!(value_after(3, 9, 10), None);
+    }
+
+    #[test]
+    fn test_value_after_zero() {
+        for step in 0..20 {
+            let mut spinlock = Spinlock::new(step);
+            for insertions in 0..300 {
+                assert_eq!(
+                    value_after_zero(step, insertions),
+                    spinlock.value_after(0).unwrap(),
+                    "step {} after {}", step, insertions
+                );
+                spinlock.insert();
+            }
+        }
+    }
 }
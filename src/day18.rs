@@ -1,207 +1,33 @@
-use nom::IResult;
-use tablet::{Op, Reg, Target, parse_op};
+use tablet::{self, Op, Profile, Scheduler, Status, Vm};
+use tablet::bytecode::Interpreter;
 use shared::AppResult;
-use std::collections::HashMap;
-use std::sync::mpsc::{Sender, Receiver};
-use std::sync::mpsc;
-use std::thread;
-use std::time::Duration;
 
 
-struct Program {
-    _pid: i64,
-    registers: HashMap<Reg, i64>,
-    ops: Vec<Op>,
-    pc: i64,
-    input: Receiver<Option<i64>>,
-    output: Sender<Option<i64>>,
+pub fn parse(input: &str) -> AppResult<Vec<Op>> {
+    tablet::parse(18, input)
 }
 
-impl Program {
-    pub fn new(
-        ops: &Vec<Op>,
-        program_id: i64,
-        input: Receiver<Option<i64>>,
-        output: Sender<Option<i64>>,
-    ) -> Self {
-        Program {
-            _pid: program_id,
-            pc: 0,
-            ops: ops.iter().cloned().collect(),
-            registers: [('p', program_id)].iter().cloned().collect(),
-            input: input,
-            output: output,
-        }
-    }
-
-    fn resolve(&self, target: &Target) -> i64 {
-        use self::Target::*;
-        match target {
-            Value(ref i) => *i,
-            Register(r) => *(self.registers.get(&r).unwrap_or(&0)),
-        }
-    }
-
-    pub fn run(&mut self) -> u32 {
-        use self::Op::*;
-        use self::Target::*;
-
-        let mut snd_count = 0;
-
-        while self.pc < self.ops.len() as i64 && self.pc >= 0 {
-            // println!("{:?}, {:?}", &self._pid, &self.ops[self.pc as usize]);
-            match &self.ops[self.pc as usize] {
-                Snd(ref t) => {
-                    let value = self.resolve(t);
-                    snd_count += 1;
-                    match self.output.send(Some(value)) {
-                        Ok(_) => {},
-                        Err(e) => {println!("failed to send: {:?}", e);},
-                    };
-                },
-                Sub(_, _) => {}, // not used
-                Jnz(_, _) => {}, // not used
-                Set(reg, t) => {
-                    let value = self.resolve(&t);
-                    self.registers.insert(*reg, value);
-                },
-                Add(x, t) => {
-                    let xval = self.resolve(&Register(*x));
-                    let tval = self.resolve(&t);
-                    self.registers.insert(*x, xval + tval);
-                },
-                Mul(x, t) => {
-                    let xval = self.resolve(&Register(*x));
-                    let tval = self.resolve(&t);
-                    self.registers.insert(*x, xval * tval);
-                },
-                Mod(x, t) => {
-                    let xval = self.resolve(&Register(*x));
-                    let tval = self.resolve(&t);
-                    self.registers.insert(*x, xval % tval);
-                },
-                Rcv(t) => {
-                    match self.input.recv_timeout(Duration::from_millis(5000)) {
-                        Ok(Some(value)) => {self.registers.insert(*t, value);},
-                        Ok(_) => panic!("shouldn't recv None atm"),
-                        Err(mpsc::RecvTimeoutError::Timeout) => {
-                            return snd_count;
-                        }
-                        Err(_) => panic!("other recv error"),
-                    }
-                },
-                Jgz(x, t) => {
-                    let xval = self.resolve(&x);
-                    let tval = self.resolve(&t);
-                    if xval > 0 {
-                        self.pc = self.pc + tval - 1;
-                    }
-                },
-            }
-            self.pc += 1;
-        }
-    snd_count
+pub fn part1(input: &str) -> AppResult<i64> {
+    let mut interpreter = Interpreter::new(&parse(input)?, Profile::Sound)?;
+    match interpreter.run()? {
+        Status::Recovered(sound) => Ok(sound),
+        status => bail!("program stopped without recovering a sound ({:?})", status),
     }
 }
 
-fn run(program: &[Op]) -> i64 {
-    use self::Op::*;
-    use self::Target::*;
-
-    let mut pc: i64 = 0;
-    let mut latest_snd = 0;
-    let mut registers = HashMap::new();
-
-    while pc < program.len() as i64 && pc >= 0 {
-        println!("{}, {:?}", latest_snd, &registers);
-        println!("{:?}", &program[pc as usize]);
-        match &program[pc as usize] {
-            Snd(ref t) => {latest_snd = resolve(t, &registers);},
-            Set(reg, t) => {
-                let value = resolve(&t, &registers);
-                registers.insert(*reg, value);
-            },
-            Add(x, t) => {
-                let xval = resolve(&Register(*x), &registers);
-                let tval = resolve(&t, &registers);
-                registers.insert(*x, xval + tval);
-            },
-            Sub(_, _) => {}, // not used
-            Jnz(_, _) => {}, // not used
-            Mul(x, t) => {
-                let xval = resolve(&Register(*x), &registers);
-                let tval = resolve(&t, &registers);
-                registers.insert(*x, xval * tval);
-            },
-            Mod(x, t) => {
-                let xval = resolve(&Register(*x), &registers);
-                let tval = resolve(&t, &registers);
-                registers.insert(*x, xval % tval);
-            },
-            Rcv(t) => {
-                let tval = resolve(&Register(*t), &registers);
-                if tval != 0 {
-                    return latest_snd;
-                }
-            },
-            Jgz(x, t) => {
-                let xval = resolve(x, &registers);
-                let tval = resolve(&t, &registers);
-                if xval > 0 {
-                    pc = pc + tval - 1;
-                }
-            },
-        }
-        pc += 1;
-    }
-    panic!("program ended")
-}
 
-fn resolve(target: &Target, registers: &HashMap<Reg, i64>) -> i64 {
-    use self::Target::*;
-    match target {
-        Value(ref i) => *i,
-        Register(r) => *(registers.get(&r).unwrap_or(&0)),
-    }
-}
-
-fn parse(input: &str) -> Vec<Op> {
-    input.split('\n')
-        .filter_map(|line| match parse_op(line.as_bytes()) {
-            IResult::Done(_, p) => Some(p),
-            _ => panic!("parsing failed"),
+pub fn part2(input: &str) -> AppResult<u64> {
+    let instructions = parse(input)?;
+    let programs = (0..2)
+        .map(|id| {
+            let mut vm = Vm::new(&instructions, Profile::Duet)?;
+            vm.registers.set("p", id);
+            Ok(vm)
         })
-        .collect()
-}
-
-pub fn part1(input: &str) -> AppResult<u32> {
-    let program = parse(input);
-    // println!("{:?}", program);
-    Ok(run(&program) as u32)
-}
-
-
-pub fn part2(input: &str) -> AppResult<u32> {
-    let instructions = parse(input);
-    let (p0t, p1r) = mpsc::channel();
-    let (p1t, p0r) = mpsc::channel();
-    let mut p0 = Program::new(
-        &instructions,
-        0,
-        p0r,
-        p0t,
-    );
-    let mut p1 = Program::new(
-        &instructions,
-        1,
-        p1r,
-        p1t,
-    );
-    let run1 = thread::spawn(move || p1.run());
-    let run0 = thread::spawn(move || p0.run());
-    run0.join().expect("t0 failed");
-    let result = run1.join().expect("t1 failed");
-    Ok(result)
+        .collect::<AppResult<_>>()?;
+    let mut scheduler = Scheduler::new(programs);
+    scheduler.run()?;
+    Ok(scheduler.sent(1))
 }
 
 
@@ -223,7 +49,7 @@ set a 1
 jgz a -2").unwrap(), 4);
     }
 
-    #[test] #[ignore]
+    #[test]
     fn test_part2() {
         assert_eq!(part2("snd 1
 snd 2
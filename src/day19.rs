@@ -1,4 +1,5 @@
 use direction::{Direction, Location};
+use parsers::ParseError;
 use shared::AppResult;
 use position::Position;
 
@@ -12,100 +13,105 @@ impl Map {
         Map{map: map}
     }
 
+    // off the edge of the diagram is empty space
     pub fn get(&self, position: Position) -> char {
-        self.map[position.y as usize][position.x as usize]
+        if position.x < 0 || position.y < 0 {
+            return ' ';
+        }
+        self.map.get(position.y as usize)
+            .and_then(|row| row.get(position.x as usize))
+            .cloned()
+            .unwrap_or(' ')
     }
 }
 
 
-fn parse(input: &str) -> Map {
-    Map::new(input.split('\n').map(|line| line.chars().collect()).collect())
+fn parse(input: &str) -> AppResult<Map> {
+    for (y, line) in input.split('\n').enumerate() {
+        for (x, c) in line.chars().enumerate() {
+            match c {
+                ' ' | '|' | '-' | '+' | 'A'..='Z' => {},
+                _ => return Err(ParseError::new(19, y + 1, x + 1, line, "a path, a letter or a space").into()),
+            }
+        }
+    }
+    let map = Map::new(input.split('\n').map(|line| line.chars().collect()).collect());
+    start(&map)?;
+    Ok(map)
 }
 
-fn start(map: &Map) -> Location {
+fn start(map: &Map) -> AppResult<Location> {
     use self::Direction::*;
-    if let Some(col) = map.map[0].iter().position(|&c| c != ' ') {
-        return Location { position: Position::new(col as i32, 0), direction: Down};
+    match map.map[0].iter().position(|&c| c != ' ') {
+        Some(col) => Ok(Location { position: Position::new(col as i32, 0), direction: Down}),
+        None => {
+            let line: String = map.map[0].iter().collect();
+            Err(ParseError::new(19, 1, 1, &line, "the start of the path in the first row").into())
+        },
     }
-    unreachable!("no start found");
 }
 
-fn step(map: &Map, start: Location) -> Location {
+fn step(map: &Map, start: Location) -> AppResult<Location> {
     use self::Direction::*;
     let Location{position, direction} = start;
     if map.get(position) == '+' {
-        if direction.is_vertical() {
-            if map.get(position + Position::new(-1, 0)) != ' ' {
-                return Location {
-                    position: Position::new(position.x - 1, position.y),
-                    direction: Left,
-                }
-            }
-            if map.get(position + Position::new(1, 0)) != ' ' {
-                return Location {
-                    position: Position::new(position.x + 1, position.y),
-                    direction: Right,
-                }
-            }
-            panic!("lost");
-        }
-        else {
-            if map.get(position + Position::new(0, -1)) != ' ' {
-                return Location {
-                    position: position + Position::new(0, -1),
-                    direction: Up,
-                }
+        let turns = if direction.is_vertical() {
+            [(Position::new(-1, 0), Left), (Position::new(1, 0), Right)]
+        } else {
+            [(Position::new(0, -1), Up), (Position::new(0, 1), Down)]
+        };
+        for (offset, direction) in &turns {
+            if map.get(position + *offset) != ' ' {
+                return Ok(Location {
+                    position: position + *offset,
+                    direction: direction.clone(),
+                });
             }
-            if map.get(position + Position::new(0, 1)) != ' ' {
-                return Location {
-                    position: position + Position::new(0, 1),
-                    direction: Down,
-                }
-            }
-            panic!("lost");
         }
-
+        bail!("dead end at line {}, column {}", position.y + 1, position.x + 1);
     }
-    return Location {
+    Ok(Location {
         position: position + direction.as_offset(),
-        direction: direction,
-    }
+        direction,
+    })
 }
 
-fn walk(map: &Map, start: Location) -> (Vec<char>, u32) {
+fn walk(map: &Map, start: Location) -> AppResult<(Vec<char>, u32)> {
+    // a path crosses each square at most twice, once each way, unless it
+    // runs round in a circle
+    let limit: usize = 2 * map.map.iter().map(|row| row.len()).sum::<usize>();
     let mut letters = vec![];
     let mut pos = start;
     let mut steps = 0;
     loop {
-        // println!("{:?}", pos);
         let current = map.get(pos.position);
-        // println!("current {:?}", current);
-        if current >= 'A' && current <= 'Z' {
+        if current.is_ascii_uppercase() {
             letters.push(current);
         }
         if current == ' ' {
             break;
         }
+        if steps as usize > limit {
+            bail!("the path never ends");
+        }
 
-        pos = step(&map, pos);
+        pos = step(map, pos)?;
         steps += 1;
     }
 
-    (letters, steps)
+    Ok((letters, steps))
 }
 
-pub fn part1(input: &str) -> AppResult<u32> {
-    let map = parse(input);
-    let letters = walk(&map, start(&map)).0;
-    let s: String = letters.iter().collect();
-    println!("{}", s);
-    Ok(0)
+pub fn part1(input: &str) -> AppResult<String> {
+    let map = parse(input)?;
+    let letters = walk(&map, start(&map)?)?.0;
+    Ok(letters.iter().collect())
 }
 
 
 pub fn part2(input: &str) -> AppResult<u32> {
-    let map = parse(input);
-    let steps = walk(&map, start(&map)).1;
+    let map = parse(input)?;
+    let steps = walk(&map, start(&map)?)?.1;
     Ok(steps)
 }
 
@@ -126,19 +132,24 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(SAMPLE).unwrap(), 0);
+        assert_eq!(part1(SAMPLE).unwrap(), "ABCDEF");
     }
 
     #[test]
     fn test_parse() {
-        let map = parse(SAMPLE);
+        let map = parse(SAMPLE).unwrap();
 
         assert_eq!(map.get(Position::new(5, 2)), 'A');
+        assert_eq!(map.get(Position::new(-1, 2)), ' ');
+        assert_eq!(map.get(Position::new(5, 20)), ' ');
+        let err = parse("  |\n  x").err().unwrap().downcast::<ParseError>().unwrap();
+        assert_eq!((err.line, err.column), (2, 3));
+        assert!(parse("   \n  |").is_err());
     }
 
     #[test]
     fn test_start() {
-        assert_eq!(start(&parse(SAMPLE)), Location{
+        assert_eq!(start(&parse(SAMPLE).unwrap()).unwrap(), Location{
                 position: Position::new(5, 0),
                 direction: Direction::Down,
             });
@@ -146,10 +157,19 @@ mod tests {
 
     #[test]
     fn test_walk() {
-        let map = parse(SAMPLE);
+        let map = parse(SAMPLE).unwrap();
         assert_eq!(
-            walk(&map, start(&map)),
+            walk(&map, start(&map).unwrap()).unwrap(),
             (vec!['A', 'B', 'C', 'D', 'E', 'F'], 38)
         )
     }
+
+    #[test]
+    fn test_lost() {
+        assert_eq!(part2(" |\n |\n +").unwrap_err().to_string(), "dead end at line 3, column 2");
+        assert_eq!(part1("|\n+").unwrap_err().to_string(), "dead end at line 2, column 1");
+        // the walk runs off the edge of the diagram and stops
+        assert_eq!(part1("|\nA").unwrap(), "A");
+        assert!(part2(" |\n+++\n+++").is_err());
+    }
 }
@@ -1,5 +1,4 @@
-use nom::IResult;
-use parsers::integer;
+use parsers::{integer, parse_lines};
 use shared::AppResult;
 use std::collections::HashMap;
 use std::ops;
@@ -30,6 +29,10 @@ named!(parse <Particle>,
     )
 );
 
+fn parse_particles(input: &str) -> AppResult<Vec<Particle>> {
+    parse_lines(20, input, "`p=<x,y,z>, v=<x,y,z>, a=<x,y,z>`", parse)
+}
+
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 struct Particle {
@@ -93,12 +96,7 @@ impl ops::Add for Coor {
 
 
 pub fn part1(input: &str) -> AppResult<u32> {
-    let rows = input.split('\n')
-        .filter_map(|row| match parse(row.as_bytes()) {
-            IResult::Done(_, entry) => Some(entry),
-            o => panic!("parsing failed {:?}", o),
-        })
-        .collect::<Vec<_>>();
+    let rows = parse_particles(input)?;
     let mut largest_val = 0;
     let mut largest_pos = 0;
 
@@ -137,25 +135,13 @@ enum PState {
 pub fn part2(input: &str) -> AppResult<u32> {
     use self::PState::*;
 
-    let mut particles = input.split('\n')
-        .filter_map(|row| match parse(row.as_bytes()) {
-            IResult::Done(_, entry) => Some(entry),
-            o => panic!("parsing failed {:?}", o),
-        })
+    let mut particles = parse_particles(input)?
+        .into_iter()
         .map(|p| Alive(p))
         .collect::<Vec<_>>();
 
     loop {
         let mut positions = HashMap::new();
-        println!("{:?}", particles.iter().map(|p| match p {
-            Alive(_) => Coor::new(1,0,0),
-            Overflowed => Coor::new(0,1,0),
-            Collided => Coor::new(0,0,1),
-        }).fold(Coor::new(0,0,0), |acc, x| acc + x));
-        println!("ov {}", particles.iter().filter(|p| match p {
-            Overflowed => true,
-            _ => false,
-        }).count());
         if !particles.iter().any(|p| match p {
             Alive(_) => true,
             _ => false,
@@ -209,6 +195,7 @@ pub fn part2(input: &str) -> AppResult<u32> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use nom::IResult;
 
     #[test]
     fn test_parse() {
@@ -219,6 +206,11 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_particles() {
+        assert!(parse_particles("p=<3,0,0>, v=<2,0,0>, a=<-1,0,0>\np=<4,0,0>, v=<0,0>").is_err());
+    }
+
     #[test]
     fn test_part1() {
         assert_eq!(part1("p=<3,0,0>, v=<2,0,0>, a=<-1,0,0>
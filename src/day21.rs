@@ -1,5 +1,7 @@
 use std::ops;
+use std::str;
 use std::collections::HashMap;
+use parsers::{map_lines, parse_line, ParseError};
 use shared::AppResult;
 
 // const START: [[char;3];3] = [['.', '#', '.'],['.', '.', '#'], ['#', '#', '#']];
@@ -88,27 +90,27 @@ impl Pattern {
 
     }
 
-    pub fn replace(&self, replacements: &HashMap<Pattern, Pattern>) -> Pattern {
-        // println!("looking for {}", self.as_str());
-        for (key, ref value) in replacements.iter() {
-            // println!("next: {}", key.as_str());
+    // tries every rotation and flip of the pattern against the rules
+    pub fn replace(&self, replacements: &HashMap<Pattern, Pattern>) -> AppResult<Pattern> {
+        for (key, value) in replacements.iter() {
             let mut possibility = self.clone();
-            for _ in 0..3 {
-                // println!("trying {}", possibility.as_str());
+            for _ in 0..4 {
                 if possibility == *key || possibility.fliplr() == *key || possibility.flipud() == *key {
-                    // println!("found match");
-                    return (*value).clone();
+                    return Ok(value.clone());
                 }
-                // println!("rotating {} to {}", possibility.as_str(), possibility.rotate().as_str());
                 possibility = possibility.rotate();
             }
         }
-        panic!("no match found: {:?}", self);
+        bail!("no rule matches {}", self.as_str());
     }
 
     pub fn count(&self) -> u32 {
         self.source.iter().map(|line| line.iter().filter(|&c| *c == '#').count()).sum::<usize>() as u32
     }
+
+    pub fn is_square(&self, size: usize) -> bool {
+        self.source.len() == size && self.source.iter().all(|row| row.len() == size)
+    }
 }
 
 impl ops::Index<(usize, usize)> for Pattern {
@@ -120,44 +122,61 @@ impl ops::Index<(usize, usize)> for Pattern {
 }
 
 
-fn parse(input: &str) -> HashMap<Pattern,Pattern> {
-    let mut patterns = HashMap::new();
-    for line in input.split('\n') {
-        let mut parts = line.split(' ');
-        let lhs = parts.nth(0).expect("invalid pattern");
-        let rhs = parts.nth(1).expect("invalid pattern");
-        patterns.insert(Pattern::from_str(lhs), Pattern::from_str(rhs));
-    }
-
-    patterns
+named!(pattern <Pattern>,
+    map!(map_res!(is_a!(".#/"), str::from_utf8), Pattern::from_str)
+);
+
+named!(rule <(Pattern, Pattern)>,
+    do_parse!(
+        lhs: pattern >>
+        tag!(" => ") >>
+        rhs: pattern >>
+        ((lhs, rhs))
+    )
+);
+
+// a rule turns a 2x2 pattern into a 3x3 one, or a 3x3 into a 4x4
+fn parse(input: &str) -> AppResult<HashMap<Pattern,Pattern>> {
+    let rules = map_lines(input, |line, text| {
+        let (lhs, rhs) = parse_line(21, line, text, "`pattern => pattern`", rule)?;
+        let size = lhs.source.len();
+        if !(size == 2 || size == 3) || !lhs.is_square(size) {
+            return Err(ParseError::new(21, line, 1, text, "a 2x2 or 3x3 pattern").into());
+        }
+        if !rhs.is_square(size + 1) {
+            let column = text.find(" => ").map_or(1, |index| index + 5);
+            let expected = format!("a {0}x{0} pattern", size + 1);
+            return Err(ParseError::new(21, line, column, text, &expected).into());
+        }
+        Ok((lhs, rhs))
+    })?;
+    Ok(rules.into_iter().collect())
 }
 
-fn run(input: &str, iterations: usize) -> u32 {
-    let replacements = parse(input);
+fn run(input: &str, iterations: usize) -> AppResult<u32> {
+    let replacements = parse(input)?;
     let mut current = Pattern::from_str(START);
-    println!("current: {}", current.as_str());
-    for it in 0..iterations {
-        println!("iteration: {}", it);
+    for _ in 0..iterations {
         // let foo = Pattern::from_str("#..#/..../..../#..#");
         let side_length = if current.source.len() % 2 == 0 {2} else {3};
         // println!("{:?}", current.split(side_length));
-        let parts: Vec<_> = current.split(side_length).iter().map(
-            |v| v.iter().map(|p| p.replace(&replacements)).collect::<Vec<_>>()
-        ).collect();
+        let parts = current.split(side_length).iter().map(
+            |v| v.iter().map(|p| p.replace(&replacements)).collect::<AppResult<Vec<_>>>()
+        ).collect::<AppResult<Vec<_>>>()?;
         current = Pattern::combine(parts);
         // println!("{:?}", parts);
     }
-    current.count()
+    Ok(current.count())
 }
 
 
 pub fn part1(input: &str) -> AppResult<u32> {
-    Ok(run(input, 5))
+    run(input, 5)
 }
 
 
 pub fn part2(input: &str) -> AppResult<u32> {
-    Ok(run(input, 18))
+    run(input, 18)
 }
 
 
@@ -208,6 +227,27 @@ mod tests {
     fn test_part1() {
         println!("");
         assert_eq!(run("../.# => ##./#../...
-.#./..#/### => #..#/..../..../#..#", 2), 12);
+.#./..#/### => #..#/..../..../#..#", 2).unwrap(), 12);
+    }
+
+    #[test]
+    fn test_missing_rule() {
+        let err = run("../.# => ##./#../...", 1).unwrap_err();
+        assert!(err.to_string().starts_with("no rule matches "));
+        // the start only matches this rule once rotated three times
+        assert_eq!(run("###/..#/.#. => #..#/..../..../#..#", 1).unwrap(), 4);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("../.# => ##./#../...").unwrap().get(&Pattern::from_str("../.#")),
+            Some(&Pattern::from_str("##./#../..."))
+        );
+        assert!(parse("../.# -> ##./#../...").is_err());
+        let column = |input| parse(input).unwrap_err().downcast::<ParseError>().unwrap().column;
+        assert_eq!(column("##./#.. => ##./#../..."), 1);
+        assert_eq!(column("#/. => ##/.."), 1);
+        assert_eq!(column("../.# => ##./#../..../...."), 10);
     }
 }
@@ -1,4 +1,5 @@
 use std::collections::HashSet;
+use parsers::ParseError;
 use shared::AppResult;
 use position::Position;
 use direction::{Direction, Location};
@@ -11,26 +12,28 @@ struct Map2 {
     flagged: HashSet<Position>,
 }
 
-fn parse(input: &str) -> HashSet<Position>{
+fn parse(input: &str) -> AppResult<HashSet<Position>> {
     let mut map = HashSet::new();
 
     for (y, row) in input.split('\n').enumerate() {
         let offset = row.len() as i32 / 2;
         for (x, value) in row.chars().enumerate() {
-            if value == '#' {
-                map.insert(Position::new(x as i32 - offset, y as i32 - offset));
+            match value {
+                '#' => {map.insert(Position::new(x as i32 - offset, y as i32 - offset));},
+                '.' => {},
+                _ => return Err(ParseError::new(22, y + 1, x + 1, row, "`#` or `.`").into()),
             }
         }
     }
-    map
+    Ok(map)
 }
 
-fn parse2(input: &str) -> Map2 {
-    Map2 {
+fn parse2(input: &str) -> AppResult<Map2> {
+    Ok(Map2 {
         weakened: HashSet::new(),
-        infected: parse(input),
+        infected: parse(input)?,
         flagged: HashSet::new(),
-    }
+    })
 }
 
 fn turn_left(direction: Direction) -> Direction {
@@ -133,12 +136,12 @@ fn walk(mut map: Map, steps: usize) -> usize {
 }
 
 pub fn part1(input: &str) -> AppResult<u32> {
-    Ok(walk(parse(input), 10000) as u32)
+    Ok(walk(parse(input)?, 10000) as u32)
 }
 
 
 pub fn part2(input: &str) -> AppResult<u32> {
-    Ok(walk2(parse2(input), 10_000_000) as u32)
+    Ok(walk2(parse2(input)?, 10_000_000) as u32)
 }
 
 
@@ -151,28 +154,33 @@ mod tests {
         assert_eq!(
             parse("..#
 #..
-..."),
+...").unwrap(),
             [Position::new(1, -1), Position::new(-1, 0)].iter().cloned().collect()
     )
     }
 
+    #[test]
+    fn test_parse_error() {
+        assert!(parse("..#\n#.x\n...").is_err());
+    }
+
     #[test]
     fn test_walk() {
         assert_eq!(walk(parse("..#
 #..
-..."), 70), 41);
+...").unwrap(), 70), 41);
         assert_eq!(walk(parse("..#
 #..
-..."), 10000), 5587);
+...").unwrap(), 10000), 5587);
     }
 
     #[test] #[ignore]
     fn test_walk2() {
         assert_eq!(walk2(parse2("..#
 #..
-..."), 100), 26);
+...").unwrap(), 100), 26);
         assert_eq!(walk2(parse2("..#
 #..
-..."), 10000000), 2511944);
+...").unwrap(), 10000000), 2511944);
     }
 }
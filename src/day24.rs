@@ -1,21 +1,24 @@
+use parsers::{parse_lines, positive_integer};
 use shared::AppResult;
 
-fn parse(input: &str) -> Vec<(u32, u32)> {
-    let mut result = vec![];
-    for line in input.split('\n') {
-        let mut parts = line.split('/');
-        let a = parts.next().expect("parsing failed").parse().expect("p fail");
-        let b = parts.next().expect("parsing failed").parse().expect("p fail");
-        result.push((a, b));
-    }
-    result
+named!(component <(u32, u32)>,
+    do_parse!(
+        a: positive_integer >>
+        tag!("/") >>
+        b: positive_integer >>
+        ((a, b))
+    )
+);
+
+fn parse(input: &str) -> AppResult<Vec<(u32, u32)>> {
+    parse_lines(24, input, "`port/port`", component)
 }
 
 fn build(parts: Vec<(u32, u32)>, start: u32) -> u32 {
     let options = parts
         .iter()
         .enumerate()
-        .filter(|(_, &(x, y))| *x == start || *y == start);
+        .filter(|&(_, &(x, y))| x == start || y == start);
     let mut strongest = 0;
     for (index, &(x, y)) in options {
         let mut next_parts = parts.clone();
@@ -33,7 +36,7 @@ fn build2(parts: Vec<(u32, u32)>, start: u32) -> (u32, u32) {
     let options = parts
         .iter()
         .enumerate()
-        .filter(|(_, &(x, y))| *x == start || *y == start);
+        .filter(|&(_, &(x, y))| x == start || y == start);
     let mut longest_strongest = (0, 0);
     for (index, &(x, y)) in options {
         let mut next_parts = parts.clone();
@@ -49,12 +52,12 @@ fn build2(parts: Vec<(u32, u32)>, start: u32) -> (u32, u32) {
 }
 
 pub fn part1(input: &str) -> AppResult<u32> {
-    Ok(build(parse(input), 0))
+    Ok(build(parse(input)?, 0))
 }
 
 
 pub fn part2(input: &str) -> AppResult<u32> {
-    Ok(build2(parse(input), 0).1)
+    Ok(build2(parse(input)?, 0).1)
 }
 
 
@@ -73,7 +76,8 @@ mod tests {
 
     #[test]
     fn test_parsing() {
-        assert_eq!(parse(SAMPLE), vec![(0,2), (2,2), (2,3), (3,4), (3,5), (0,1), (10,1), (9,10)]);
+        assert_eq!(parse(SAMPLE).unwrap(), vec![(0,2), (2,2), (2,3), (3,4), (3,5), (0,1), (10,1), (9,10)]);
+        assert!(parse("0/2\n2-2").is_err());
     }
 
     #[test]
@@ -1,139 +1,232 @@
-use std::collections::HashSet;
+use std::cmp;
+use std::collections::HashMap;
+use std::str;
+use nom::alpha;
+use parsers::{parse_all, positive_integer};
 use shared::AppResult;
 
-enum State {
-    A,
-    B,
-    C,
-    D,
-    E,
-    F,
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Action {
+    pub write: u32,
+    pub offset: i32,
+    pub next: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Blueprint {
+    pub start: String,
+    pub steps: u32,
+    pub states: Vec<(String, Vec<(u32, Action)>)>,
+}
+
+
+named!(state_name <String>,
+    map!(map_res!(alpha, str::from_utf8), String::from)
+);
+
+named!(action <Action>,
+    ws!(do_parse!(
+        tag!("- Write the value") >>
+        write: positive_integer >>
+        tag!(".") >>
+        tag!("- Move one slot to the") >>
+        offset: alt!(
+            tag!("right") => {|_| 1} |
+            tag!("left") => {|_| -1}
+        ) >>
+        tag!(".") >>
+        tag!("- Continue with state") >>
+        next: state_name >>
+        tag!(".") >>
+        (Action {write, offset, next})
+    ))
+);
+
+named!(case <(u32, Action)>,
+    ws!(do_parse!(
+        tag!("If the current value is") >>
+        value: positive_integer >>
+        tag!(":") >>
+        action: action >>
+        ((value, action))
+    ))
+);
+
+named!(state <(String, Vec<(u32, Action)>)>,
+    ws!(do_parse!(
+        tag!("In state") >>
+        name: state_name >>
+        tag!(":") >>
+        cases: many1!(complete!(case)) >>
+        ((name, cases))
+    ))
+);
+
+named!(blueprint <Blueprint>,
+    ws!(do_parse!(
+        tag!("Begin in state") >>
+        start: state_name >>
+        tag!(".") >>
+        tag!("Perform a diagnostic checksum after") >>
+        steps: positive_integer >>
+        tag!("steps.") >>
+        states: many1!(complete!(state)) >>
+        (Blueprint {start, steps, states})
+    ))
+);
+
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Transition {
+    write: u32,
+    offset: i32,
+    next: usize,
 }
 
-struct Machine {
-    pos: i32,
-    ones: HashSet<i32>,
-    state: State,
+
+// dense tape that grows in either direction, doubling its size as needed
+#[derive(Debug, PartialEq)]
+pub struct Tape {
+    cells: Vec<u32>,
+    head: usize,
 }
 
-impl Machine {
+impl Tape {
     pub fn new() -> Self {
-        Machine {
-            pos: 0,
-            ones: HashSet::new(),
-            state: State::A,
+        Tape {
+            cells: vec![0; 64],
+            head: 32,
         }
     }
 
-    fn is_zero(&self) -> bool {
-        !self.ones.contains(&self.pos)
+    pub fn read(&self) -> u32 {
+        self.cells[self.head]
+    }
+
+    pub fn write(&mut self, symbol: u32) {
+        self.cells[self.head] = symbol;
     }
 
-    fn write(&mut self, value: usize) {
-        match value {
-            0 => {self.ones.remove(&self.pos);},
-            1 => {self.ones.insert(self.pos);},
-            _ => {panic!("invalid write value");},
+    pub fn shift(&mut self, offset: i32) {
+        if offset < 0 {
+            let distance = (-offset) as usize;
+            if distance > self.head {
+                let grow = cmp::max(self.cells.len(), distance);
+                let mut cells = vec![0; grow];
+                cells.append(&mut self.cells);
+                self.cells = cells;
+                self.head += grow;
+            }
+            self.head -= distance;
+        } else {
+            self.head += offset as usize;
+            if self.head >= self.cells.len() {
+                let grow = cmp::max(self.cells.len(), self.head + 1 - self.cells.len());
+                let len = self.cells.len();
+                self.cells.resize(len + grow, 0);
+            }
         }
     }
 
-    fn move_right(&mut self) {
-        self.pos += 1;
+    // number of non-blank cells
+    pub fn checksum(&self) -> usize {
+        self.cells.iter().filter(|&&c| c != 0).count()
     }
+}
 
-    fn move_left(&mut self) {
-        self.pos -= 1;
+impl Default for Tape {
+    fn default() -> Self {
+        Tape::new()
     }
+}
 
-    pub fn step(&mut self) {
-        match self.state {
-            State::A => {
-                if self.is_zero() {
-                    self.write(1);
-                    self.move_right();
-                    self.state = State::B;
-                }
-                else {
-                    self.write(0);
-                    self.move_left();
-                    self.state = State::E;
-                }
-            },
-            State::B => {
-                if self.is_zero() {
-                    self.write(1);
-                    self.move_left();
-                    self.state = State::C;
-                }
-                else {
-                    self.write(0);
-                    self.move_right();
-                    self.state = State::A;
-                }
-            },
-            State::C => {
-                if self.is_zero() {
-                    self.write(1);
-                    self.move_left();
-                    self.state = State::D;
-                }
-                else {
-                    self.write(0);
-                    self.move_right();
-                    self.state = State::C;
-                }
-            },
-            State::D => {
-                if self.is_zero() {
-                    self.write(1);
-                    self.move_left();
-                    self.state = State::E;
-                }
-                else {
-                    self.write(0);
-                    self.move_left();
-                    self.state = State::F;
-                }
-            },
-            State::E => {
-                if self.is_zero() {
-                    self.write(1);
-                    self.move_left();
-                    self.state = State::A;
-                }
-                else {
-                    self.write(1);
-                    self.move_left();
-                    self.state = State::C;
-                }
-            },
-            State::F => {
-                if self.is_zero() {
-                    self.write(1);
-                    self.move_left();
-                    self.state = State::E;
+
+pub struct TuringMachine {
+    pub tape: Tape,
+    state: usize,
+    names: Vec<String>,
+    // indexed by state, then by the symbol under the head
+    rules: Vec<Vec<Option<Transition>>>,
+}
+
+impl TuringMachine {
+    pub fn new(blueprint: &Blueprint) -> AppResult<Self> {
+        let index: HashMap<_, _> = blueprint.states
+            .iter()
+            .enumerate()
+            .map(|(i, (name, _))| (name.as_str(), i))
+            .collect();
+        if index.len() != blueprint.states.len() {
+            bail!("state defined more than once");
+        }
+        let lookup = |name: &str| index
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format_err!("unknown state `{}`", name));
+
+        let mut rules = vec![];
+        for (name, cases) in &blueprint.states {
+            let mut transitions = vec![];
+            for (value, action) in cases {
+                let value = *value as usize;
+                if value >= transitions.len() {
+                    transitions.resize(value + 1, None);
                 }
-                else {
-                    self.write(1);
-                    self.move_right();
-                    self.state = State::A;
+                if transitions[value].is_some() {
+                    bail!("state `{}` has more than one rule for {}", name, value);
                 }
-            },
+                transitions[value] = Some(Transition {
+                    write: action.write,
+                    offset: action.offset,
+                    next: lookup(&action.next)?,
+                });
+            }
+            rules.push(transitions);
         }
+
+        Ok(TuringMachine {
+            tape: Tape::new(),
+            state: lookup(&blueprint.start)?,
+            names: blueprint.states.iter().map(|(name, _)| name.clone()).collect(),
+            rules,
+        })
+    }
+
+    pub fn state(&self) -> &str {
+        &self.names[self.state]
+    }
+
+    pub fn step(&mut self) -> AppResult<()> {
+        let symbol = self.tape.read();
+        let transition = match self.rules[self.state].get(symbol as usize) {
+            Some(&Some(transition)) => transition,
+            _ => bail!("state `{}` has no rule for {}", self.state(), symbol),
+        };
+        self.tape.write(transition.write);
+        self.tape.shift(transition.offset);
+        self.state = transition.next;
+        Ok(())
     }
-}
 
-pub fn part1(_input: &str) -> AppResult<u32> {
-    let mut machine = Machine::new();
-    for _ in 0..12386363 {
-        machine.step();
+    pub fn run(&mut self, steps: u32) -> AppResult<()> {
+        for _ in 0..steps {
+            self.step()?;
+        }
+        Ok(())
     }
-    Ok(machine.ones.len() as u32)
 }
 
 
-pub fn part2(_input: &str) -> AppResult<u32> {
-    Ok(0)
+pub fn parse(input: &str) -> AppResult<Blueprint> {
+    parse_all(25, input, "a Turing machine blueprint", blueprint)
+}
+
+pub fn part1(input: &str) -> AppResult<usize> {
+    let blueprint = parse(input)?;
+    let mut machine = TuringMachine::new(&blueprint)?;
+    machine.run(blueprint.steps)?;
+    Ok(machine.tape.checksum())
 }
 
 
@@ -141,8 +234,105 @@ pub fn part2(_input: &str) -> AppResult<u32> {
 mod tests {
     use super::*;
 
+    const SAMPLE: &str = "Begin in state A.
+Perform a diagnostic checksum after 6 steps.
+
+In state A:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state B.
+  If the current value is 1:
+    - Write the value 0.
+    - Move one slot to the left.
+    - Continue with state B.
+
+In state B:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the left.
+    - Continue with state A.
+  If the current value is 1:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state A.
+";
+
+    #[test]
+    fn test_parse() {
+        let blueprint = parse(SAMPLE).unwrap();
+        assert_eq!(blueprint.start, "A");
+        assert_eq!(blueprint.steps, 6);
+        assert_eq!(blueprint.states.len(), 2);
+        assert_eq!(blueprint.states[1].1[0], (0, Action {write: 1, offset: -1, next: "A".into()}));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let input = SAMPLE.replace("slot to the left.\n    - Continue with state A", "slot up.\n    - Continue with state A");
+        assert!(part1(&input).is_err());
+        assert!(part1(&SAMPLE.replace("state B.\n  If", "state C.\n  If")).is_err());
+    }
+
+    #[test]
+    fn test_tape() {
+        let mut tape = Tape::new();
+        tape.write(1);
+        tape.shift(-100);
+        tape.write(2);
+        tape.shift(250);
+        tape.write(3);
+        tape.shift(-150);
+        assert_eq!(tape.read(), 1);
+        tape.shift(-100);
+        assert_eq!(tape.read(), 2);
+        assert_eq!(tape.checksum(), 3);
+    }
+
+    #[test]
+    fn test_three_symbols() {
+        // 0 -> 1 -> 2 -> 0, stepping back out to the left after each write
+        let blueprint = parse("Begin in state A.
+Perform a diagnostic checksum after 8 steps.
+
+In state A:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the left.
+    - Continue with state B.
+  If the current value is 1:
+    - Write the value 2.
+    - Move one slot to the left.
+    - Continue with state B.
+  If the current value is 2:
+    - Write the value 0.
+    - Move one slot to the right.
+    - Continue with state A.
+
+In state B:
+  If the current value is 0:
+    - Write the value 0.
+    - Move one slot to the right.
+    - Continue with state A.
+").unwrap();
+        let mut machine = TuringMachine::new(&blueprint).unwrap();
+        machine.run(blueprint.steps).unwrap();
+        assert_eq!(machine.state(), "B");
+        assert_eq!(machine.tape.read(), 0);
+        machine.step().unwrap();
+        assert_eq!(machine.tape.read(), 2);
+        assert_eq!(machine.tape.checksum(), 1);
+    }
+
+    #[test]
+    fn test_missing_rule() {
+        let blueprint = parse(&SAMPLE.replace("value is 1", "value is 2")).unwrap();
+        let mut machine = TuringMachine::new(&blueprint).unwrap();
+        assert!(machine.run(blueprint.steps).is_err());
+    }
+
     #[test]
     fn test_part1() {
-        assert_eq!(part1("").unwrap(), 0);
+        assert_eq!(part1(SAMPLE).unwrap(), 3);
     }
 }
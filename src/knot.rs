@@ -0,0 +1,207 @@
+// the knot hash from day 10, used again by day 14
+
+use shared::AppResult;
+
+
+const SUFFIX: [u8; 5] = [17, 31, 73, 47, 23];
+const ROUNDS: usize = 64;
+
+
+// the knot-tying engine: a list of `size` marks, twisted by each length in
+// turn, then by the suffix, for `rounds` rounds
+#[derive(Debug, PartialEq, Clone)]
+pub struct Knot {
+    pub size: usize,
+    pub rounds: usize,
+    pub suffix: Vec<usize>,
+}
+
+// the parameters of the full hash
+impl Default for Knot {
+    fn default() -> Self {
+        Knot {
+            size: 256,
+            rounds: ROUNDS,
+            suffix: SUFFIX.iter().map(|&x| x as usize).collect(),
+        }
+    }
+}
+
+impl Knot {
+    // a single round with no suffix, as in day 10 part 1
+    pub fn simple(size: usize) -> Self {
+        Knot { size, rounds: 1, suffix: vec![] }
+    }
+
+    // the marks in list order, starting from the first position
+    pub fn tie(&self, lengths: &[usize]) -> AppResult<Vec<usize>> {
+        if self.size == 0 {
+            bail!("can't tie knots in an empty list");
+        }
+        let lengths: Vec<_> = lengths.iter().chain(&self.suffix).cloned().collect();
+        if let Some(&length) = lengths.iter().find(|&&length| length > self.size) {
+            bail!("length {} is longer than the list of {}", length, self.size);
+        }
+        let mut list: Vec<usize> = (0..self.size).collect();
+        let (mut pos, mut skip) = (0, 0);
+        for _ in 0..self.rounds {
+            for &length in &lengths {
+                reverse(&mut list, pos, length);
+                pos = (pos + length + skip) % self.size;
+                skip = (skip + 1) % self.size;
+            }
+        }
+        Ok(list)
+    }
+}
+
+// reverses `length` elements starting at `start`, wrapping round the end
+fn reverse(list: &mut [usize], start: usize, length: usize) {
+    let size = list.len();
+    for i in 0..length / 2 {
+        list.swap((start + i) % size, (start + length - 1 - i) % size);
+    }
+}
+
+
+// every round reuses the whole input, so `update` only buffers it and the
+// knots are tied in `finalize`
+#[derive(Debug, Default, Clone)]
+pub struct KnotHasher {
+    input: Vec<u8>,
+}
+
+impl KnotHasher {
+    pub fn new() -> Self {
+        KnotHasher::default()
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.input.extend_from_slice(bytes);
+    }
+
+    pub fn finalize(self) -> [u8; 16] {
+        let lengths: Vec<_> = self.input.iter().map(|&x| x as usize).collect();
+        let list = Knot::default().tie(&lengths).expect("bytes are never longer than 256");
+
+        let mut digest = [0; 16];
+        for (byte, chunk) in digest.iter_mut().zip(list.chunks(16)) {
+            *byte = chunk.iter().fold(0, |x, y| x ^ y) as u8;
+        }
+        digest
+    }
+}
+
+
+pub fn hash(bytes: &[u8]) -> [u8; 16] {
+    let mut hasher = KnotHasher::new();
+    hasher.update(bytes);
+    hasher.finalize()
+}
+
+pub fn hex(digest: &[u8; 16]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+
+// the bits of a digest, most significant first
+pub struct Bits {
+    digest: [u8; 16],
+    index: usize,
+}
+
+impl Iterator for Bits {
+    type Item = bool;
+
+    fn next(&mut self) -> Option<bool> {
+        if self.index == 128 {
+            return None;
+        }
+        let bit = self.digest[self.index / 8] & (0x80 >> (self.index % 8)) != 0;
+        self.index += 1;
+        Some(bit)
+    }
+}
+
+pub fn bits(digest: &[u8; 16]) -> Bits {
+    Bits { digest: *digest, index: 0 }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex_hash(input: &str) -> String {
+        hex(&hash(input.as_bytes()))
+    }
+
+    #[test]
+    fn test_hash() {
+        assert_eq!(hex_hash(""), "a2582a3a0e66e6e86e3812dcb672a272");
+        assert_eq!(hex_hash("AoC 2017"), "33efeb34ea91902bb2f59c9920caa6cd");
+        assert_eq!(hex_hash("1,2,3"), "3efbe78a8d82f29979031a4aa0b16a9d");
+        assert_eq!(hex_hash("1,2,4"), "63960835bcdc130f0b66d7ff4f6a5a8e");
+    }
+
+    #[test]
+    fn test_update() {
+        let mut hasher = KnotHasher::new();
+        hasher.update(b"AoC");
+        hasher.update(b"");
+        hasher.update(b" 2017");
+        assert_eq!(hasher.finalize(), hash(b"AoC 2017"));
+    }
+
+    #[test]
+    fn test_bits() {
+        // the first row of day 14's example starts `##.#.#..`
+        let digest = hash(b"flqrgnkx-0");
+        let row: String = bits(&digest).take(8).map(|bit| if bit { '#' } else { '.' }).collect();
+        assert_eq!(row, "##.#.#..");
+        assert_eq!(bits(&digest).count(), 128);
+        assert_eq!(
+            bits(&digest).filter(|&bit| bit).count() as u32,
+            digest.iter().map(|byte| byte.count_ones()).sum::<u32>()
+        );
+    }
+
+    #[test]
+    fn test_tie() {
+        assert_eq!(Knot::simple(5).tie(&[3, 4, 1, 5]).unwrap(), vec![3, 4, 2, 1, 0]);
+        assert_eq!(Knot::simple(5).tie(&[]).unwrap(), vec![0, 1, 2, 3, 4]);
+        assert!(Knot::simple(5).tie(&[6]).is_err());
+        assert!(Knot::simple(0).tie(&[0]).is_err());
+        let knot = Knot { size: 3, rounds: 2, suffix: vec![4] };
+        assert!(knot.tie(&[1]).is_err());
+    }
+
+    // a xorshift generator, so the property tests are repeatable
+    fn random(state: &mut u64, below: usize) -> usize {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        (*state % below as u64) as usize
+    }
+
+    #[test]
+    fn test_permutation() {
+        let mut state = 2017;
+        for _ in 0..500 {
+            let size = 1 + random(&mut state, 40);
+            let mut lengths = |count| (0..count).map(|_| random(&mut state, size + 1)).collect::<Vec<_>>();
+            let (input, suffix) = (lengths(8), lengths(3));
+            let knot = Knot { size, rounds: random(&mut state, 5), suffix };
+            let mut list = knot.tie(&input).unwrap();
+            list.sort();
+            assert_eq!(list, (0..size).collect::<Vec<_>>(), "{:?} with {:?}", knot, input);
+        }
+    }
+
+    #[test]
+    fn test_reverse() {
+        let mut list = [0, 1, 2, 3, 4];
+        reverse(&mut list, 3, 4);
+        assert_eq!(list, [4, 3, 2, 1, 0]);
+    }
+}
@@ -0,0 +1,43 @@
+//! Advent of Code 2017 solutions, plus the pieces they share: the tablet
+//! assembly VM, grid geometry, input parsers and the day registry used by
+//! the `aoc2017` binary.
+
+#[macro_use] extern crate failure;
+// extern crate itertools;
+#[macro_use] extern crate nom;
+
+pub mod answers;
+pub mod cycle;
+pub mod direction;
+pub mod knot;
+pub mod parsers;
+pub mod position;
+pub mod runner;
+pub mod shared;
+pub mod solution;
+pub mod tablet;
+pub mod day01;
+pub mod day02;
+pub mod day03;
+pub mod day04;
+pub mod day05;
+pub mod day06;
+pub mod day07;
+pub mod day08;
+pub mod day09;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day18;
+pub mod day19;
+pub mod day20;
+pub mod day21;
+pub mod day22;
+pub mod day23;
+pub mod day24;
+pub mod day25;
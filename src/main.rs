@@ -1,58 +1,30 @@
-#![feature(conservative_impl_trait)]
-#![feature(match_default_bindings)]
-#![feature(slice_rotate)]
-#![feature(universal_impl_trait)]
-
-
+extern crate aoc2017;
 extern crate clap;
 #[macro_use] extern crate failure;
-// extern crate itertools;
-#[macro_use] extern crate nom;
 
-use clap::{App, Arg};
+use aoc2017::{answers, day06, day14, runner, shared, solution, tablet};
+use clap::{App, AppSettings, Arg, SubCommand};
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter};
 use std::path::Path;
-
-mod direction;
-mod parsers;
-mod position;
-mod shared;
-mod tablet;
-mod day01;
-mod day02;
-mod day03;
-mod day04;
-mod day05;
-mod day06;
-mod day07;
-mod day08;
-mod day09;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
-mod day16;
-mod day17;
-mod day18;
-mod day19;
-mod day20;
-mod day21;
-mod day22;
-mod day23;
-mod day24;
-mod day25;
+use std::process;
+use std::time::Duration;
 
 fn main() {
     match run() {
-        Ok(result) => println!("{}", result),
-        Err(err) => println!("{}", err),
+        Ok(Some(result)) => println!("{}", result),
+        Ok(None) => {},
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        },
     };
 }
 
 
-fn run() -> shared::AppResult<u32> {
+fn run() -> shared::AppResult<Option<shared::Answer>> {
     let matches = App::new("adventofcode")
+        .setting(AppSettings::SubcommandsNegateReqs)
         .arg(Arg::with_name("day")
             .short("d")
             .takes_value(true)
@@ -64,76 +36,334 @@ fn run() -> shared::AppResult<u32> {
             .default_value("1")
             .possible_values(&["1", "2"])
         )
+        .arg(Arg::with_name("threads")
+            .long("threads")
+            .takes_value(true)
+            .help("Number of worker threads for days that use them [default: one per CPU]")
+        )
         .arg(Arg::with_name("input")
             .help("Sets the input file to use, or `-` for stdin")
             .required(true)
             .index(1))
+        .subcommand(SubCommand::with_name("list")
+            .about("Lists the available days")
+        )
+        .subcommand(SubCommand::with_name("samples")
+            .about("Checks every day against its sample inputs")
+        )
+        .subcommand(SubCommand::with_name("run-all")
+            .about("Runs every day and part, reading inputs from `<inputs>/dayNN.txt`")
+            .arg(Arg::with_name("inputs")
+                .long("inputs")
+                .takes_value(true)
+                .default_value("inputs")
+            )
+        )
+        .subcommand(SubCommand::with_name("verify")
+            .about("Checks every day listed in an answers file (`day part answer` lines)")
+            .arg(Arg::with_name("answers")
+                .long("answers")
+                .takes_value(true)
+                .default_value("inputs/answers.txt")
+            )
+            .arg(Arg::with_name("inputs")
+                .long("inputs")
+                .takes_value(true)
+                .default_value("inputs")
+            )
+        )
+        .subcommand(SubCommand::with_name("disasm")
+            .about("Disassembles a tablet program (days 18 and 23) into basic blocks")
+            .arg(Arg::with_name("dot")
+                .long("dot")
+                .help("Prints the control-flow graph as Graphviz DOT instead")
+            )
+            .arg(Arg::with_name("day")
+                .short("d")
+                .takes_value(true)
+                .default_value("23")
+                .possible_values(&["18", "23"])
+            )
+            .arg(Arg::with_name("input")
+                .help("Sets the input file to use, or `-` for stdin")
+                .required(true)
+                .index(1))
+        )
+        .subcommand(SubCommand::with_name("reallocate")
+            .about("Traces the day 6 memory banks until a configuration repeats")
+            .arg(Arg::with_name("input")
+                .help("Sets the input file to use, or `-` for stdin")
+                .required(true)
+                .index(1))
+        )
+        .subcommand(SubCommand::with_name("regions")
+            .about("Draws the day 14 disk with its regions labelled, then lists their sizes")
+            .arg(Arg::with_name("ppm")
+                .long("ppm")
+                .help("Prints a PPM image instead, one pixel per square")
+            )
+            .arg(Arg::with_name("threads")
+                .long("threads")
+                .takes_value(true)
+                .help("Number of threads hashing the rows [default: one per CPU]")
+            )
+            .arg(Arg::with_name("input")
+                .help("Sets the input file to use, or `-` for stdin")
+                .required(true)
+                .index(1))
+        )
+        .subcommand(SubCommand::with_name("debug")
+            .about("Steps through a tablet program, reading debugger commands from stdin")
+            .arg(Arg::with_name("day")
+                .short("d")
+                .takes_value(true)
+                .default_value("18")
+                .possible_values(&["18", "23"])
+            )
+            .arg(Arg::with_name("part")
+                .short("p")
+                .takes_value(true)
+                .default_value("1")
+                .possible_values(&["1", "2"])
+            )
+            .arg(Arg::with_name("trace")
+                .long("trace")
+                .takes_value(true)
+                .help("Logs every executed instruction to this file")
+            )
+            .arg(Arg::with_name("limit")
+                .long("limit")
+                .takes_value(true)
+                .help("Stops after this many instructions")
+            )
+            .arg(Arg::with_name("overflow")
+                .long("overflow")
+                .takes_value(true)
+                .default_value("checked")
+                .possible_values(&["checked", "wrapping", "saturating"])
+                .help("What arithmetic does when a result doesn't fit in an i64")
+            )
+            .arg(Arg::with_name("input")
+                .help("Sets the program file to debug")
+                .required(true)
+                .index(1))
+        )
         .get_matches();
 
-    let input = match matches
-        .value_of("input")
-        .expect("input is required but missing")
-    {
-        "-" => shared::read_stdin(),
-        filename => shared::read_file(Path::new(filename)),
-    }?;
-
-    match (
-        matches.value_of("day").ok_or(format_err!("Invalid day"))?.parse()?,
-        matches.value_of("part").ok_or(format_err!("Invalid part"))?.parse()?
-    ) {
-        (1, 1) => day01::part1(&input),
-        (1, 2) => day01::part2(&input),
-        (2, 1) => day02::part1(&input),
-        (2, 2) => day02::part2(&input),
-        (3, 1) => day03::part1(&input),
-        (3, 2) => day03::part2(&input),
-        (4, 1) => day04::part1(&input),
-        (4, 2) => day04::part2(&input),
-        (5, 1) => day05::part1(&input),
-        (5, 2) => day05::part2(&input),
-        (6, 1) => day06::part1(&input),
-        (6, 2) => day06::part2(&input),
-        (7, 1) => day07::part1(&input),
-        (7, 2) => day07::part2(&input),
-        (8, 1) => day08::part1(&input),
-        (8, 2) => day08::part2(&input),
-        (9, 1) => day09::part1(&input),
-        (9, 2) => day09::part2(&input),
-        (10, 1) => day10::part1(&input),
-        (10, 2) => day10::part2(&input),
-        (11, 1) => day11::part1(&input),
-        (11, 2) => day11::part2(&input),
-        (12, 1) => day12::part1(&input),
-        (12, 2) => day12::part2(&input),
-        (13, 1) => day13::part1(&input),
-        (13, 2) => day13::part2(&input),
-        (14, 1) => day14::part1(&input),
-        (14, 2) => day14::part2(&input),
-        (15, 1) => day15::part1(&input),
-        (15, 2) => day15::part2(&input),
-        (16, 1) => day16::part1(&input),
-        (16, 2) => day16::part2(&input),
-        (17, 1) => day17::part1(&input),
-        (17, 2) => day17::part2(&input),
-        (18, 1) => day18::part1(&input),
-        (18, 2) => day18::part2(&input),
-        (19, 1) => day19::part1(&input),
-        (19, 2) => day19::part2(&input),
-        (20, 1) => day20::part1(&input),
-        (20, 2) => day20::part2(&input),
-        (21, 1) => day21::part1(&input),
-        (21, 2) => day21::part2(&input),
-        (22, 1) => day22::part1(&input),
-        (22, 2) => day22::part2(&input),
-        (23, 1) => day23::part1(&input),
-        (23, 2) => day23::part2(&input),
-        (24, 1) => day24::part1(&input),
-        (24, 2) => day24::part2(&input),
-        (25, 1) => day25::part1(&input),
-        (25, 2) => day25::part2(&input),
-        (d, 1) => bail!("Invalid problem `{}`", d),
-        (d, 2) => bail!("Invalid problem `{}`", d),
-        p => bail!("Invalid problem spec `{:?}`", p),
+    if matches.subcommand_matches("list").is_some() {
+        for solution in solution::registry() {
+            println!("{:2}  {}", solution.day(), solution.title());
+        }
+        return Ok(None);
+    }
+
+    if matches.subcommand_matches("samples").is_some() {
+        return check_samples().map(|_| None);
+    }
+
+    if let Some(matches) = matches.subcommand_matches("run-all") {
+        let inputs = matches.value_of("inputs").expect("inputs has a default");
+        return run_all(Path::new(inputs)).map(|_| None);
+    }
+
+    if let Some(matches) = matches.subcommand_matches("verify") {
+        let answers = matches.value_of("answers").expect("answers has a default");
+        let inputs = matches.value_of("inputs").expect("inputs has a default");
+        return verify(Path::new(answers), Path::new(inputs)).map(|_| None);
+    }
+
+    if let Some(matches) = matches.subcommand_matches("disasm") {
+        let input = shared::read_input(Path::new(
+            matches.value_of("input").expect("input is required but missing")
+        ))?;
+        let day = matches.value_of("day").expect("day has a default").parse()?;
+        let ops = tablet::parse(day, &input)?;
+        if matches.is_present("dot") {
+            print!("{}", tablet::disasm::dot(&ops));
+        } else {
+            print!("{}", tablet::disasm::listing(&ops));
+        }
+        return Ok(None);
+    }
+
+    if let Some(matches) = matches.subcommand_matches("reallocate") {
+        let input = shared::read_input(Path::new(
+            matches.value_of("input").expect("input is required but missing")
+        ))?;
+        print!("{}", day06::trace(&input)?);
+        return Ok(None);
+    }
+
+    if let Some(matches) = matches.subcommand_matches("regions") {
+        let input = shared::read_input(Path::new(
+            matches.value_of("input").expect("input is required but missing")
+        ))?;
+        let threads = threads(matches)?.unwrap_or_else(day14::default_threads);
+        let regions = day14::Grid::with_threads(&input, threads).regions();
+        if matches.is_present("ppm") {
+            print!("{}", regions.ppm());
+        } else {
+            print!("{}", regions.ascii());
+            let mut sizes = regions.sizes().to_vec();
+            sizes.sort_by(|a, b| b.cmp(a));
+            let sizes: Vec<_> = sizes.iter().map(|size| size.to_string()).collect();
+            println!("{} regions, sizes {}", regions.count(), sizes.join(" "));
+        }
+        return Ok(None);
+    }
+
+    if let Some(matches) = matches.subcommand_matches("debug") {
+        return debug(matches).map(|_| None);
+    }
+
+    let input = shared::read_input(Path::new(
+        matches.value_of("input").expect("input is required but missing")
+    ))?;
+
+    let day = matches.value_of("day").ok_or(format_err!("Invalid day"))?.parse()?;
+    let solution = solution::find(day)?;
+    let part = matches.value_of("part").ok_or(format_err!("Invalid part"))?.parse()?;
+    let options = solution::Options { threads: threads(&matches)? };
+    solution.part_with(part, &input, &options).map(Some)
+}
+
+
+fn threads(matches: &clap::ArgMatches) -> shared::AppResult<Option<usize>> {
+    match matches.value_of("threads") {
+        Some(threads) => match threads.parse()? {
+            0 => bail!("--threads must be at least 1"),
+            threads => Ok(Some(threads)),
+        },
+        None => Ok(None),
+    }
+}
+
+
+fn check_samples() -> shared::AppResult<()> {
+    let mut failures = 0;
+    for solution in solution::registry() {
+        for sample in solution.samples() {
+            let status = match solution.part(sample.part, sample.input) {
+                Ok(ref answer) if answer.to_string() == sample.answer => "ok".into(),
+                Ok(answer) => {
+                    failures += 1;
+                    format!("FAILED: expected {}, got {}", sample.answer, answer)
+                },
+                Err(err) => {
+                    failures += 1;
+                    format!("FAILED: {}", err)
+                },
+            };
+            println!("day {:2} part {}: {}", solution.day(), sample.part, status);
+        }
+    }
+    if failures > 0 {
+        bail!("{} sample(s) failed", failures);
+    }
+    Ok(())
+}
+
+
+fn run_all(inputs: &Path) -> shared::AppResult<()> {
+    let mut failures = 0;
+    let mut total = Duration::new(0, 0);
+    runner::silence_panics();
+    println!("day  part          time  answer");
+    for solution in solution::registry() {
+        for outcome in runner::run_day(&*solution, inputs) {
+            let answer = match outcome.result {
+                Ok(ref answer) => answer.to_string(),
+                Err(ref err) => format!("ERROR: {}", err),
+            };
+            if !outcome.is_ok() {
+                failures += 1;
+            }
+            total += outcome.elapsed;
+            println!(
+                "{:>3}  {:>4}  {:>12}  {}",
+                outcome.day,
+                outcome.part,
+                runner::format_elapsed(outcome.elapsed),
+                answer,
+            );
+        }
+    }
+    println!("           {:>12}  total", runner::format_elapsed(total));
+    if failures > 0 {
+        bail!("{} part(s) failed", failures);
+    }
+    Ok(())
+}
+
+
+fn verify(answers: &Path, inputs: &Path) -> shared::AppResult<()> {
+    let answers = answers::parse(&shared::read_input(answers)?)?;
+    let mut failures = 0;
+    runner::silence_panics();
+    println!("day  part          time  status");
+    for solution in solution::registry() {
+        if !answers.has_day(solution.day()) {
+            continue;
+        }
+        for outcome in runner::run_day(&*solution, inputs) {
+            let status = answers::check(&outcome, answers.get(outcome.day, outcome.part));
+            if status.is_failure() {
+                failures += 1;
+            }
+            println!(
+                "{:>3}  {:>4}  {:>12}  {}",
+                outcome.day,
+                outcome.part,
+                runner::format_elapsed(outcome.elapsed),
+                status,
+            );
+        }
+    }
+    if failures > 0 {
+        bail!("{} part(s) failed verification", failures);
+    }
+    Ok(())
+}
+
+
+// commands: step [n], continue, break/delete <pc>, watch/unwatch <reg>,
+// limit <steps>, regs, set <reg> <value>, send <value>, where, quit
+fn debug(matches: &clap::ArgMatches) -> shared::AppResult<()> {
+    let input = shared::read_input(Path::new(
+        matches.value_of("input").expect("input is required but missing")
+    ))?;
+    let day = matches.value_of("day").expect("day has a default").parse()?;
+    let profile = match (day, matches.value_of("part")) {
+        (23, _) => tablet::Profile::Coprocessor,
+        (_, Some("2")) => tablet::Profile::Duet,
+        _ => tablet::Profile::Sound,
+    };
+    let overflow = match matches.value_of("overflow") {
+        Some("wrapping") => tablet::Overflow::Wrapping,
+        Some("saturating") => tablet::Overflow::Saturating,
+        _ => tablet::Overflow::Checked,
+    };
+    let config = tablet::Config { overflow, ..tablet::Config::default() };
+    let mut debugger = tablet::debug::Debugger::new(
+        tablet::Vm::with_config(&tablet::parse(day, &input)?, profile, config)?
+    );
+    if let Some(path) = matches.value_of("trace") {
+        let file = File::create(path).map_err(|err| format_err!("{}: {}", path, err))?;
+        debugger.trace_to(Box::new(BufWriter::new(file)));
+    }
+    if let Some(limit) = matches.value_of("limit") {
+        debugger.limit(Some(limit.parse()?));
+    }
+
+    println!("{}", debugger.location());
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        match debugger.execute(&line?) {
+            Ok(Some(ref output)) if output.is_empty() => {},
+            Ok(Some(output)) => println!("{}", output),
+            Ok(None) => break,
+            Err(err) => eprintln!("error: {}", err),
+        }
     }
+    Ok(())
 }
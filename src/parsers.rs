@@ -1,5 +1,9 @@
+use std::fmt;
 use std::str::{self, FromStr};
-use nom::digit;
+use failure::Fail;
+use nom::{digit, Err, IResult};
+
+use shared::AppResult;
 
 named!(pub positive_integer <u32>,
     map_res!(
@@ -21,6 +25,186 @@ named!(pub integer <i32>,
 );
 
 
+// parsed as a whole, so out-of-range values are errors rather than wrapping
+named!(pub long_integer <i64>,
+    map_res!(
+        map_res!(recognize!(pair!(opt!(complete!(tag!("-"))), digit)), str::from_utf8),
+        FromStr::from_str
+    )
+);
+
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    pub day: u32,
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    pub expected: String,
+}
+
+impl ParseError {
+    pub fn new(day: u32, line: usize, column: usize, text: &str, expected: &str) -> Self {
+        ParseError {
+            day,
+            line,
+            column,
+            text: text.into(),
+            expected: expected.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f, "day {}: parse error at line {}, column {}: expected {}",
+            self.day, self.line, self.column, self.expected
+        )?;
+        writeln!(f, "    {}", self.text)?;
+        write!(f, "    {:>1$}", "^", self.column)
+    }
+}
+
+impl Fail for ParseError {}
+
+
+// byte offset of the furthest position nom reported, if any
+fn error_offset(line: &[u8], err: &Err<&[u8]>) -> Option<usize> {
+    match *err {
+        Err::Code(_) => None,
+        Err::Node(_, ref errs) => errs.iter().filter_map(|e| error_offset(line, e)).max(),
+        Err::Position(_, p) => Some(line.len() - p.len()),
+        Err::NodePosition(_, p, ref errs) => errs.iter()
+            .filter_map(|e| error_offset(line, e))
+            .chain(Some(line.len() - p.len()))
+            .max(),
+    }
+}
+
+
+pub fn parse_field<T, F>(
+    day: u32, line: usize, text: &str, offset: usize, field: &str, expected: &str, parser: F
+) -> AppResult<T>
+    where F: Fn(&[u8]) -> IResult<&[u8], T>
+{
+    let bytes = field.as_bytes();
+    let column = match parser(bytes) {
+        IResult::Done(rest, value) => {
+            if rest.is_empty() {
+                return Ok(value);
+            }
+            bytes.len() - rest.len()
+        },
+        IResult::Error(err) => error_offset(bytes, &err).unwrap_or(0),
+        IResult::Incomplete(_) => bytes.len(),
+    };
+    Err(ParseError::new(day, line, offset + column + 1, text, expected).into())
+}
+
+
+pub fn parse_line<T, F>(day: u32, line: usize, text: &str, expected: &str, parser: F) -> AppResult<T>
+    where F: Fn(&[u8]) -> IResult<&[u8], T>
+{
+    parse_field(day, line, text, 0, text, expected, parser)
+}
+
+
+// for parsers that consume the whole multi-line input at once
+pub fn parse_all<T, F>(day: u32, input: &str, expected: &str, parser: F) -> AppResult<T>
+    where F: Fn(&[u8]) -> IResult<&[u8], T>
+{
+    let bytes = input.as_bytes();
+    let offset = match parser(bytes) {
+        IResult::Done(rest, value) => {
+            if rest.is_empty() {
+                return Ok(value);
+            }
+            bytes.len() - rest.len()
+        },
+        IResult::Error(err) => error_offset(bytes, &err).unwrap_or(0),
+        IResult::Incomplete(_) => bytes.len(),
+    };
+    let line_start = input[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let line = input[line_start..].split('\n').next().unwrap_or("");
+    let number = input[..offset].matches('\n').count() + 1;
+    Err(ParseError::new(day, number, offset - line_start + 1, line, expected).into())
+}
+
+
+pub fn map_lines<T, F>(input: &str, f: F) -> AppResult<Vec<T>>
+    where F: Fn(usize, &str) -> AppResult<T>
+{
+    input.split('\n')
+        .enumerate()
+        .map(|(index, line)| f(index + 1, line))
+        .collect()
+}
+
+
+pub fn parse_lines<T, F>(day: u32, input: &str, expected: &str, parser: F) -> AppResult<Vec<T>>
+    where F: Fn(&[u8]) -> IResult<&[u8], T>
+{
+    map_lines(input, |line, text| parse_line(day, line, text, expected, &parser))
+}
+
+
+pub fn parse_value<T: FromStr>(day: u32, line: usize, text: &str, expected: &str) -> AppResult<T> {
+    text.parse()
+        .map_err(|_| ParseError::new(day, line, 1, text, expected).into())
+}
+
+
+fn fields<P>(text: &str, is_separator: P) -> Vec<(usize, &str)>
+    where P: Fn(char) -> bool
+{
+    let mut result = vec![];
+    let mut start = 0;
+    for (index, c) in text.char_indices().chain(Some((text.len(), ' '))) {
+        if index == text.len() || is_separator(c) {
+            result.push((start, &text[start..index]));
+            start = index + c.len_utf8();
+        }
+    }
+    result
+}
+
+
+pub fn parse_words<T: FromStr>(day: u32, line: usize, text: &str, expected: &str) -> AppResult<Vec<T>> {
+    fields(text, char::is_whitespace)
+        .into_iter()
+        .filter(|&(_, field)| !field.is_empty())
+        .map(|(offset, field)| field.parse().map_err(
+            |_| ParseError::new(day, line, offset + 1, text, expected).into()
+        ))
+        .collect()
+}
+
+
+pub fn parse_separated<T, F>(
+    day: u32, line: usize, text: &str, separator: char, expected: &str, parser: F
+) -> AppResult<Vec<T>>
+    where F: Fn(&[u8]) -> IResult<&[u8], T>
+{
+    fields(text, |c| c == separator)
+        .into_iter()
+        .map(|(offset, field)| parse_field(day, line, text, offset, field, expected, &parser))
+        .collect()
+}
+
+
+pub fn parse_separated_values<T: FromStr>(
+    day: u32, line: usize, text: &str, separator: char, expected: &str
+) -> AppResult<Vec<T>> {
+    fields(text, |c| c == separator)
+        .into_iter()
+        .map(|(offset, field)| field.parse().map_err(
+            |_| ParseError::new(day, line, offset + 1, text, expected).into()
+        ))
+        .collect()
+}
+
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -49,4 +233,68 @@ mod tests {
             IResult::Done(&b""[..], -42)
         );
     }
+
+    #[test]
+    fn test_long_integer() {
+        assert_eq!(long_integer(&b"3000000000"[..]), IResult::Done(&b""[..], 3_000_000_000));
+        assert_eq!(long_integer(&b"-9223372036854775808"[..]), IResult::Done(&b""[..], i64::MIN));
+        assert!(long_integer(&b"9223372036854775808"[..]).is_err());
+    }
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(parse_line(1, 1, "42", "a number", positive_integer).unwrap(), 42);
+        let err = parse_line(1, 3, "42x", "a number", positive_integer).unwrap_err();
+        assert_eq!(
+            err.downcast::<ParseError>().unwrap(),
+            ParseError::new(1, 3, 3, "42x", "a number")
+        );
+    }
+
+    #[test]
+    fn test_parse_error_display() {
+        assert_eq!(
+            ParseError::new(8, 2, 3, "a dex 1", "`inc` or `dec`").to_string(),
+            "day 8: parse error at line 2, column 3: expected `inc` or `dec`\n    a dex 1\n      ^"
+        );
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        assert_eq!(parse_lines(1, "1\n-2", "an integer", integer).unwrap(), vec![1, -2]);
+        let err = parse_lines(1, "1\n\n3", "an integer", integer).unwrap_err();
+        let err = err.downcast::<ParseError>().unwrap();
+        assert_eq!((err.line, err.column), (2, 1));
+    }
+
+    #[test]
+    fn test_parse_all() {
+        named!(pair <(u32, u32)>,
+            ws!(do_parse!(a: positive_integer >> tag!(",") >> b: positive_integer >> ((a, b))))
+        );
+        assert_eq!(parse_all(1, "1,\n 2", "a pair", pair).unwrap(), (1, 2));
+        let err = parse_all(1, "1\n  x", "a pair", pair).unwrap_err();
+        assert_eq!(err.downcast::<ParseError>().unwrap(), ParseError::new(1, 2, 3, "  x", "a pair"));
+    }
+
+    #[test]
+    fn test_parse_words() {
+        assert_eq!(parse_words::<u32>(2, 1, " 5  1\t9", "a number").unwrap(), vec![5, 1, 9]);
+        let err = parse_words::<u32>(2, 1, "5 1 x", "a number").unwrap_err();
+        assert_eq!(err.downcast::<ParseError>().unwrap().column, 5);
+    }
+
+    #[test]
+    fn test_parse_separated() {
+        assert_eq!(
+            parse_separated(10, 1, "3,4,1", ',', "a number", positive_integer).unwrap(),
+            vec![3, 4, 1]
+        );
+        let err = parse_separated(10, 1, "3,,1", ',', "a number", positive_integer).unwrap_err();
+        assert_eq!(err.downcast::<ParseError>().unwrap().column, 3);
+        assert_eq!(
+            parse_separated_values::<u32>(10, 1, "3,4", ',', "a number").unwrap(),
+            vec![3, 4]
+        );
+    }
 }
@@ -0,0 +1,144 @@
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use shared::{self, Answer};
+use solution::Solution;
+
+
+#[derive(Debug)]
+pub struct Outcome {
+    pub day: u32,
+    pub part: u32,
+    pub result: Result<Answer, String>,
+    pub elapsed: Duration,
+}
+
+impl Outcome {
+    pub fn is_ok(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+
+pub fn input_path(inputs: &Path, day: u32) -> PathBuf {
+    inputs.join(format!("day{:02}.txt", day))
+}
+
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        return format!("panicked: {}", message);
+    }
+    if let Some(message) = payload.downcast_ref::<String>() {
+        return format!("panicked: {}", message);
+    }
+    "panicked".into()
+}
+
+
+// panics are reported in the outcome, so stop the default hook printing them too
+pub fn silence_panics() {
+    panic::set_hook(Box::new(|_| {}));
+}
+
+
+pub fn run_part(solution: &dyn Solution, part: u32, input: &str) -> Outcome {
+    let start = Instant::now();
+    let result = match panic::catch_unwind(AssertUnwindSafe(|| solution.part(part, input))) {
+        Ok(Ok(answer)) => Ok(answer),
+        Ok(Err(err)) => Err(err.to_string()),
+        Err(payload) => Err(panic_message(payload)),
+    };
+    Outcome {
+        day: solution.day(),
+        part,
+        result,
+        elapsed: start.elapsed(),
+    }
+}
+
+
+pub fn run_day(solution: &dyn Solution, inputs: &Path) -> Vec<Outcome> {
+    let parts = 1..solution.parts() + 1;
+    match shared::read_input(&input_path(inputs, solution.day())) {
+        Ok(input) => parts
+            .map(|part| run_part(solution, part, &input))
+            .collect(),
+        Err(err) => parts
+            .map(|part| Outcome {
+                day: solution.day(),
+                part,
+                result: Err(err.to_string()),
+                elapsed: Duration::new(0, 0),
+            })
+            .collect(),
+    }
+}
+
+
+pub fn format_elapsed(elapsed: Duration) -> String {
+    format!("{:.3}ms", elapsed.as_secs_f64() * 1000.0)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::AppResult;
+    use solution::find;
+
+    struct Panics;
+
+    impl Solution for Panics {
+        fn day(&self) -> u32 {
+            0
+        }
+
+        fn title(&self) -> &'static str {
+            "Panics"
+        }
+
+        fn part1(&self, _input: &str) -> AppResult<Answer> {
+            panic!("oops")
+        }
+
+        fn part2(&self, _input: &str) -> AppResult<Answer> {
+            Ok(0u32.into())
+        }
+    }
+
+    #[test]
+    fn test_input_path() {
+        assert_eq!(input_path(Path::new("inputs"), 7), Path::new("inputs/day07.txt"));
+    }
+
+    #[test]
+    fn test_run_part() {
+        let outcome = run_part(&*find(1).unwrap(), 1, "1122");
+        assert_eq!(outcome.result, Ok(Answer::from(3u32)));
+    }
+
+    #[test]
+    fn test_run_part_error() {
+        let outcome = run_part(&*find(3).unwrap(), 1, "not a number");
+        assert!(!outcome.is_ok());
+    }
+
+    #[test]
+    fn test_run_part_panic() {
+        let outcome = run_part(&Panics, 1, "");
+        assert_eq!(outcome.result, Err("panicked: oops".into()));
+        assert!(run_part(&Panics, 2, "").is_ok());
+    }
+
+    #[test]
+    fn test_run_day_missing_input() {
+        let outcomes = run_day(&*find(1).unwrap(), Path::new("does-not-exist"));
+        assert_eq!(outcomes.len(), 2);
+        assert!(outcomes.iter().all(|o| !o.is_ok()));
+        // a day without a part 2 isn't run for it
+        assert_eq!(run_day(&*find(25).unwrap(), Path::new("does-not-exist")).len(), 1);
+    }
+}
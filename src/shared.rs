@@ -1,6 +1,7 @@
 // extern crate failure;
 
 // use std::error::{self, Error};
+use std::fmt;
 use std::fs::File;
 use std::io::{self, Read};
 use std::path::Path;
@@ -12,13 +13,81 @@ use failure::Error;
 pub type AppResult<T> = Result<T, Error>;
 
 
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Answer {
+    Unsigned(u64),
+    Signed(i64),
+    Big(u128),
+    Text(String),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Answer::Unsigned(n) => write!(f, "{}", n),
+            Answer::Signed(n) => write!(f, "{}", n),
+            Answer::Big(n) => write!(f, "{}", n),
+            Answer::Text(ref s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl From<u32> for Answer {
+    fn from(n: u32) -> Self {
+        Answer::Unsigned(n as u64)
+    }
+}
+
+impl From<u64> for Answer {
+    fn from(n: u64) -> Self {
+        Answer::Unsigned(n)
+    }
+}
+
+impl From<usize> for Answer {
+    fn from(n: usize) -> Self {
+        Answer::Unsigned(n as u64)
+    }
+}
+
+impl From<i32> for Answer {
+    fn from(n: i32) -> Self {
+        Answer::Signed(n as i64)
+    }
+}
+
+impl From<i64> for Answer {
+    fn from(n: i64) -> Self {
+        Answer::Signed(n)
+    }
+}
+
+impl From<u128> for Answer {
+    fn from(n: u128) -> Self {
+        Answer::Big(n)
+    }
+}
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Text(s)
+    }
+}
+
+impl<'a> From<&'a str> for Answer {
+    fn from(s: &'a str) -> Self {
+        Answer::Text(s.into())
+    }
+}
+
+
 pub fn read_stdin() -> AppResult<String> {
     let mut buffer = String::new();
     let stdin = io::stdin();
     let mut handle = stdin.lock();
 
     handle.read_to_string(&mut buffer)?;
-    Ok(buffer.into())
+    Ok(buffer)
 }
 
 
@@ -27,5 +96,40 @@ pub fn read_file(filename: &Path) -> AppResult<String> {
     let mut handle = File::open(filename)?;
 
     handle.read_to_string(&mut buffer)?;
-    Ok(buffer.into())
+    Ok(buffer)
+}
+
+
+pub fn trim_input(input: &str) -> &str {
+    input.trim_end_matches(&['\n', '\r'][..])
+}
+
+
+pub fn read_input(source: &Path) -> AppResult<String> {
+    let input = if source == Path::new("-") {
+        read_stdin()?
+    } else {
+        read_file(source).map_err(|err| format_err!("{}: {}", source.display(), err))?
+    };
+    Ok(trim_input(&input).into())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_answer_display() {
+        assert_eq!(Answer::from(42u32).to_string(), "42");
+        assert_eq!(Answer::from(-42i64).to_string(), "-42");
+        assert_eq!(Answer::from(1u128 << 100).to_string(), "1267650600228229401496703205376");
+        assert_eq!(Answer::from("tknk").to_string(), "tknk");
+    }
+
+    #[test]
+    fn test_trim_input() {
+        assert_eq!(trim_input("1 2\n3 4\n"), "1 2\n3 4");
+        assert_eq!(trim_input("  |  \r\n\n"), "  |  ");
+    }
 }
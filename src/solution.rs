@@ -0,0 +1,266 @@
+use shared::{Answer, AppResult};
+
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Sample {
+    pub part: u32,
+    pub input: &'static str,
+    pub answer: &'static str,
+}
+
+// command line settings that change how some days run, never what they
+// answer. days that have nothing to tune ignore them
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Options {
+    // worker threads, or one per CPU if unset
+    pub threads: Option<usize>,
+}
+
+
+pub trait Solution {
+    fn day(&self) -> u32;
+    fn title(&self) -> &'static str;
+    fn part1(&self, input: &str) -> AppResult<Answer>;
+    fn part2(&self, input: &str) -> AppResult<Answer>;
+
+    fn samples(&self) -> &'static [Sample] {
+        &[]
+    }
+
+    // day 25 has only one
+    fn parts(&self) -> u32 {
+        2
+    }
+
+    fn part(&self, part: u32, input: &str) -> AppResult<Answer> {
+        match part {
+            1 => self.part1(input),
+            2 if self.parts() == 2 => self.part2(input),
+            2 => bail!("day {} has no part 2", self.day()),
+            p => bail!("Invalid part `{}`", p),
+        }
+    }
+
+    fn part_with(&self, part: u32, input: &str, _options: &Options) -> AppResult<Answer> {
+        self.part(part, input)
+    }
+}
+
+
+type Solver = fn(&str, &Options) -> AppResult<Answer>;
+
+struct Day {
+    day: u32,
+    title: &'static str,
+    part1: Solver,
+    part2: Option<Solver>,
+    samples: &'static [Sample],
+}
+
+impl Solution for Day {
+    fn day(&self) -> u32 {
+        self.day
+    }
+
+    fn title(&self) -> &'static str {
+        self.title
+    }
+
+    fn part1(&self, input: &str) -> AppResult<Answer> {
+        (self.part1)(input, &Options::default())
+    }
+
+    fn part2(&self, input: &str) -> AppResult<Answer> {
+        self.part_with(2, input, &Options::default())
+    }
+
+    fn parts(&self) -> u32 {
+        if self.part2.is_some() { 2 } else { 1 }
+    }
+
+    fn part_with(&self, part: u32, input: &str, options: &Options) -> AppResult<Answer> {
+        match (part, self.part2) {
+            (1, _) => (self.part1)(input, options),
+            (2, Some(part2)) => part2(input, options),
+            (2, None) => bail!("day {} has no part 2", self.day),
+            (p, _) => bail!("Invalid part `{}`", p),
+        }
+    }
+
+    fn samples(&self) -> &'static [Sample] {
+        self.samples
+    }
+}
+
+
+// `options` is for days whose `part1_with` and `part2_with` take Options,
+// and `part1_only` for days without a part 2
+macro_rules! day {
+    ($day:expr, $module:ident, $title:expr) => (
+        day!($day, $module, $title, &[])
+    );
+    ($day:expr, $module:ident, $title:expr, $samples:expr) => (
+        Box::new(Day {
+            day: $day,
+            title: $title,
+            part1: |input, _| ::$module::part1(input).map(Answer::from),
+            part2: Some(|input, _| ::$module::part2(input).map(Answer::from)),
+            samples: $samples,
+        }) as Box<dyn Solution>
+    );
+    ($day:expr, $module:ident, $title:expr, $samples:expr, options) => (
+        Box::new(Day {
+            day: $day,
+            title: $title,
+            part1: |input, options| ::$module::part1_with(input, options).map(Answer::from),
+            part2: Some(|input, options| ::$module::part2_with(input, options).map(Answer::from)),
+            samples: $samples,
+        }) as Box<dyn Solution>
+    );
+    ($day:expr, $module:ident, $title:expr, $samples:expr, part1_only) => (
+        Box::new(Day {
+            day: $day,
+            title: $title,
+            part1: |input, _| ::$module::part1(input).map(Answer::from),
+            part2: None,
+            samples: $samples,
+        }) as Box<dyn Solution>
+    );
+}
+
+
+pub fn registry() -> Vec<Box<dyn Solution>> {
+    vec![
+        day!(1, day01, "Inverse Captcha", &[
+            Sample {part: 1, input: "91212129", answer: "9"},
+            Sample {part: 2, input: "12131415", answer: "4"},
+        ]),
+        day!(2, day02, "Corruption Checksum", &[
+            Sample {part: 1, input: "5 1 9 5\n7 5 3\n2 4 6 8", answer: "18"},
+            Sample {part: 2, input: "5 9 2 8\n9 4 7 3\n3 8 6 5", answer: "9"},
+        ]),
+        day!(3, day03, "Spiral Memory", &[
+            Sample {part: 1, input: "1024", answer: "31"},
+        ]),
+        day!(4, day04, "High-Entropy Passphrases", &[
+            Sample {part: 1, input: "aa bb cc dd ee\naa bb cc dd aa", answer: "1"},
+            Sample {part: 2, input: "abcde fghij\nabcde xyz ecdab", answer: "1"},
+        ]),
+        day!(5, day05, "A Maze of Twisty Trampolines, All Alike", &[
+            Sample {part: 1, input: "0\n3\n0\n1\n-3", answer: "5"},
+            Sample {part: 2, input: "0\n3\n0\n1\n-3", answer: "10"},
+        ]),
+        day!(6, day06, "Memory Reallocation", &[
+            Sample {part: 1, input: "0 2 7 0", answer: "5"},
+            Sample {part: 2, input: "0 2 7 0", answer: "4"},
+        ]),
+        day!(7, day07, "Recursive Circus", &[
+            Sample {part: 1, input: "pbga (66)\nxhth (57)\nebii (61)\nhavc (66)\nktlj (57)\nfwft (72) -> ktlj, cntj, xhth\nqoyq (66)\npadx (45) -> pbga, havc, qoyq\ntknk (41) -> ugml, padx, fwft\njptl (61)\nugml (68) -> gyxo, ebii, jptl\ngyxo (61)\ncntj (57)", answer: "tknk"},
+            Sample {part: 2, input: "pbga (66)\nxhth (57)\nebii (61)\nhavc (66)\nktlj (57)\nfwft (72) -> ktlj, cntj, xhth\nqoyq (66)\npadx (45) -> pbga, havc, qoyq\ntknk (41) -> ugml, padx, fwft\njptl (61)\nugml (68) -> gyxo, ebii, jptl\ngyxo (61)\ncntj (57)", answer: "60"},
+        ]),
+        day!(8, day08, "I Heard You Like Registers", &[
+            Sample {part: 1, input: "b inc 5 if a > 1\na inc 1 if b < 5\nc dec -10 if a >= 1\nc inc -20 if c == 10", answer: "1"},
+            Sample {part: 2, input: "b inc 5 if a > 1\na inc 1 if b < 5\nc dec -10 if a >= 1\nc inc -20 if c == 10", answer: "10"},
+        ]),
+        day!(9, day09, "Stream Processing", &[
+            Sample {part: 1, input: "{{<ab>},{<ab>},{<ab>},{<ab>}}", answer: "9"},
+            Sample {part: 2, input: "{<{o\"i!a,<{i<a>}", answer: "10"},
+        ]),
+        day!(10, day10, "Knot Hash", &[
+            Sample {part: 2, input: "AoC 2017", answer: "33efeb34ea91902bb2f59c9920caa6cd"},
+        ]),
+        day!(11, day11, "Hex Ed", &[
+            Sample {part: 1, input: "se,sw,se,sw,sw", answer: "3"},
+            Sample {part: 2, input: "ne,ne,sw,sw", answer: "2"},
+        ]),
+        day!(12, day12, "Digital Plumber", &[
+            Sample {part: 1, input: "0 <-> 2\n1 <-> 1\n2 <-> 0, 3, 4\n3 <-> 2, 4\n4 <-> 2, 3, 6\n5 <-> 6\n6 <-> 4, 5", answer: "6"},
+            Sample {part: 2, input: "0 <-> 2\n1 <-> 1\n2 <-> 0, 3, 4\n3 <-> 2, 4\n4 <-> 2, 3, 6\n5 <-> 6\n6 <-> 4, 5", answer: "2"},
+        ]),
+        day!(13, day13, "Packet Scanners", &[
+            Sample {part: 1, input: "0: 3\n1: 2\n4: 4\n6: 4", answer: "24"},
+            Sample {part: 2, input: "0: 3\n1: 2\n4: 4\n6: 4", answer: "10"},
+        ]),
+        day!(14, day14, "Disk Defragmentation", &[
+            Sample {part: 1, input: "flqrgnkx", answer: "8108"},
+            Sample {part: 2, input: "flqrgnkx", answer: "1242"},
+        ], options),
+        day!(15, day15, "Dueling Generators"),
+        day!(16, day16, "Permutation Promenade"),
+        day!(17, day17, "Spinlock"),
+        day!(18, day18, "Duet", &[
+            Sample {part: 1, input: "set a 1\nadd a 2\nmul a a\nmod a 5\nsnd a\nset a 0\nrcv a\njgz a -1\nset a 1\njgz a -2", answer: "4"},
+        ]),
+        day!(19, day19, "A Series of Tubes", &[
+            Sample {part: 1, input: "     |          \n     |  +--+    \n     A  |  C    \n F---|----E|--+ \n     |  |  |  D \n     +B-+  +--+ \n                \n", answer: "ABCDEF"},
+            Sample {part: 2, input: "     |          \n     |  +--+    \n     A  |  C    \n F---|----E|--+ \n     |  |  |  D \n     +B-+  +--+ \n                \n", answer: "38"},
+        ]),
+        day!(20, day20, "Particle Swarm", &[
+            Sample {part: 1, input: "p=<3,0,0>, v=<2,0,0>, a=<-1,0,0>\np=<4,0,0>, v=<0,0,0>, a=<-2,0,0>", answer: "0"},
+        ]),
+        day!(21, day21, "Fractal Art"),
+        day!(22, day22, "Sporifica Virus", &[
+            Sample {part: 1, input: "..#\n#..\n...", answer: "5587"},
+        ]),
+        day!(23, day23, "Coprocessor Conflagration"),
+        day!(24, day24, "Electromagnetic Moat", &[
+            Sample {part: 1, input: "0/2\n2/2\n2/3\n3/4\n3/5\n0/1\n10/1\n9/10", answer: "31"},
+            Sample {part: 2, input: "0/2\n2/2\n2/3\n3/4\n3/5\n0/1\n10/1\n9/10", answer: "19"},
+        ]),
+        day!(25, day25, "The Halting Problem", &[
+            Sample {part: 1, input: "Begin in state A.\nPerform a diagnostic checksum after 6 steps.\n\nIn state A:\n  If the current value is 0:\n    - Write the value 1.\n    - Move one slot to the right.\n    - Continue with state B.\n  If the current value is 1:\n    - Write the value 0.\n    - Move one slot to the left.\n    - Continue with state B.\n\nIn state B:\n  If the current value is 0:\n    - Write the value 1.\n    - Move one slot to the left.\n    - Continue with state A.\n  If the current value is 1:\n    - Write the value 1.\n    - Move one slot to the right.\n    - Continue with state A.", answer: "3"},
+        ], part1_only),
+    ]
+}
+
+
+pub fn find(day: u32) -> AppResult<Box<dyn Solution>> {
+    registry()
+        .into_iter()
+        .find(|solution| solution.day() == day)
+        .ok_or(format_err!("Invalid problem `{}`", day))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        let days: Vec<_> = registry().iter().map(|s| s.day()).collect();
+        assert_eq!(days, (1..26).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_find() {
+        assert_eq!(find(7).unwrap().title(), "Recursive Circus");
+        assert!(find(26).is_err());
+    }
+
+    #[test]
+    fn test_part() {
+        let solution = find(1).unwrap();
+        assert_eq!(solution.part(1, "1122").unwrap(), Answer::from(3u32));
+        assert!(solution.part(3, "1122").is_err());
+        let options = Options { threads: Some(3) };
+        let last = find(25).unwrap();
+        assert_eq!(last.parts(), 1);
+        assert_eq!(last.part(2, "").unwrap_err().to_string(), "day 25 has no part 2");
+        assert!(last.part_with(2, "", &options).is_err());
+        assert_eq!(solution.part_with(1, "1122", &options).unwrap(), Answer::from(3u32));
+    }
+
+    #[test]
+    fn test_samples() {
+        for solution in registry() {
+            for sample in solution.samples() {
+                assert_eq!(
+                    solution.part(sample.part, sample.input).unwrap().to_string(),
+                    sample.answer,
+                    "day {} part {}", solution.day(), sample.part
+                );
+            }
+        }
+    }
+}
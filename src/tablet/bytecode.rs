@@ -0,0 +1,370 @@
+use std::collections::VecDeque;
+
+use shared::AppResult;
+use super::{Op, Overflow, Profile, Reg, Status, Target};
+
+
+// an instruction with its registers resolved to slots. the `I` variants take
+// an immediate value as their last operand, and jumps on a constant
+// condition become unconditional (or a `Jmp(1)` if never taken)
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Code {
+    Snd(usize),
+    SndI(i64),
+    Set(usize, usize),
+    SetI(usize, i64),
+    Add(usize, usize),
+    AddI(usize, i64),
+    Sub(usize, usize),
+    SubI(usize, i64),
+    Mul(usize, usize),
+    MulI(usize, i64),
+    Mod(usize, usize),
+    ModI(usize, i64),
+    Rcv(usize),
+    Jmp(usize),
+    JmpI(i64),
+    Jgz(usize, usize),
+    JgzI(usize, i64),
+    Jnz(usize, usize),
+    JnzI(usize, i64),
+}
+
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Bytecode {
+    pub code: Vec<Code>,
+    // the register name of each slot, in order of first use
+    pub names: Vec<Reg>,
+}
+
+impl Bytecode {
+    pub fn slot(&self, reg: &str) -> Option<usize> {
+        self.names.iter().position(|name| name == reg)
+    }
+}
+
+
+// one code per instruction, so indices (and jump offsets) are unchanged
+pub fn lower(ops: &[Op]) -> Bytecode {
+    use super::Op::*;
+
+    let mut names: Vec<Reg> = vec![];
+    let mut slot = |reg: &Reg| match names.iter().position(|name| name == reg) {
+        Some(slot) => slot,
+        None => {
+            names.push(reg.clone());
+            names.len() - 1
+        },
+    };
+    let code = ops.iter()
+        .map(|op| {
+            // the register and immediate forms of a two operand code
+            macro_rules! binary {
+                ($x:expr, $y:expr, $reg:ident, $imm:ident) => {{
+                    let x = slot($x);
+                    match *$y {
+                        Target::Register(ref y) => Code::$reg(x, slot(y)),
+                        Target::Value(y) => Code::$imm(x, y),
+                    }
+                }};
+            }
+            match *op {
+                Snd(Target::Register(ref x)) => Code::Snd(slot(x)),
+                Snd(Target::Value(x)) => Code::SndI(x),
+                Set(ref x, ref y) => binary!(x, y, Set, SetI),
+                Add(ref x, ref y) => binary!(x, y, Add, AddI),
+                Sub(ref x, ref y) => binary!(x, y, Sub, SubI),
+                Mul(ref x, ref y) => binary!(x, y, Mul, MulI),
+                Mod(ref x, ref y) => binary!(x, y, Mod, ModI),
+                Rcv(ref x) => Code::Rcv(slot(x)),
+                Jgz(Target::Register(ref x), ref y) => binary!(x, y, Jgz, JgzI),
+                Jnz(Target::Register(ref x), ref y) => binary!(x, y, Jnz, JnzI),
+                Jgz(Target::Value(x), ref y) => jump(x > 0, y, &mut slot),
+                Jnz(Target::Value(x), ref y) => jump(x != 0, y, &mut slot),
+            }
+        })
+        .collect();
+    Bytecode { code, names }
+}
+
+fn jump<F: FnMut(&Reg) -> usize>(taken: bool, offset: &Target, slot: &mut F) -> Code {
+    match *offset {
+        _ if !taken => Code::JmpI(1),
+        Target::Register(ref y) => Code::Jmp(slot(y)),
+        Target::Value(y) => Code::JmpI(y),
+    }
+}
+
+
+// runs bytecode with the same semantics as `Vm`, minus the hooks on `Op`s and
+// the macro-ops, but much faster
+pub struct Interpreter {
+    profile: Profile,
+    overflow: Overflow,
+    ops: Vec<Op>,
+    bytecode: Bytecode,
+    registers: Vec<i64>,
+    pc: i64,
+    steps: u64,
+    sound: i64,
+    pub inbox: VecDeque<i64>,
+    pub outbox: VecDeque<i64>,
+}
+
+impl Interpreter {
+    pub fn new(ops: &[Op], profile: Profile) -> AppResult<Self> {
+        Interpreter::with_overflow(ops, profile, Overflow::default())
+    }
+
+    pub fn with_overflow(ops: &[Op], profile: Profile, overflow: Overflow) -> AppResult<Self> {
+        if let Some(index) = ops.iter().position(|op| !profile.supports(op)) {
+            bail!("instruction {}: `{}` is not part of the {:?} instruction set", index + 1, ops[index], profile);
+        }
+        let bytecode = lower(ops);
+        Ok(Interpreter {
+            profile,
+            overflow,
+            ops: ops.to_vec(),
+            registers: vec![0; bytecode.names.len()],
+            bytecode,
+            pc: 0,
+            steps: 0,
+            sound: 0,
+            inbox: VecDeque::new(),
+            outbox: VecDeque::new(),
+        })
+    }
+
+    pub fn bytecode(&self) -> &Bytecode {
+        &self.bytecode
+    }
+
+    // registers the program never mentions are always zero
+    pub fn get(&self, reg: &str) -> i64 {
+        self.bytecode.slot(reg).map_or(0, |slot| self.registers[slot])
+    }
+
+    // setting a register the program never mentions has no effect
+    pub fn set(&mut self, reg: &str, value: i64) {
+        if let Some(slot) = self.bytecode.slot(reg) {
+            self.registers[slot] = value;
+        }
+    }
+
+    pub fn pc(&self) -> i64 {
+        self.pc
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn run(&mut self) -> AppResult<Status> {
+        self.run_with(|_| {})
+    }
+
+    // runs until the program halts, blocks or recovers a sound. `hook` sees
+    // each code just before it is executed
+    pub fn run_with<F: FnMut(&Code)>(&mut self, mut hook: F) -> AppResult<Status> {
+        use self::Code::*;
+
+        let (profile, overflow) = (self.profile, self.overflow);
+        let code = &self.bytecode.code;
+        let r = &mut self.registers;
+        let mut pc = self.pc;
+        let mut steps = self.steps;
+        let outcome = loop {
+            if pc < 0 || pc >= code.len() as i64 {
+                break Ok(Status::Halted);
+            }
+            let current = &code[pc as usize];
+            if let Rcv(_) = *current {
+                if profile == Profile::Duet && self.inbox.is_empty() {
+                    break Ok(Status::Blocked);
+                }
+            }
+            hook(current);
+
+            let mut status = Status::Running;
+            let mut offset = 1;
+            // the register an arithmetic code writes, and its result if that
+            // didn't overflow
+            let result = match *current {
+                Snd(x) => send(profile, r[x], &mut self.sound, &mut self.outbox),
+                SndI(x) => send(profile, x, &mut self.sound, &mut self.outbox),
+                Set(x, y) => Some((x, Some(r[y]))),
+                SetI(x, y) => Some((x, Some(y))),
+                Add(x, y) => Some((x, overflow.add(r[x], r[y]))),
+                AddI(x, y) => Some((x, overflow.add(r[x], y))),
+                Sub(x, y) => Some((x, overflow.sub(r[x], r[y]))),
+                SubI(x, y) => Some((x, overflow.sub(r[x], y))),
+                Mul(x, y) => Some((x, overflow.mul(r[x], r[y]))),
+                MulI(x, y) => Some((x, overflow.mul(r[x], y))),
+                Mod(x, y) => match r[y] {
+                    0 => break Err(format_err!("instruction {}: modulo by zero", pc + 1)),
+                    y => Some((x, overflow.rem(r[x], y))),
+                },
+                ModI(_, 0) => break Err(format_err!("instruction {}: modulo by zero", pc + 1)),
+                ModI(x, y) => Some((x, overflow.rem(r[x], y))),
+                Rcv(x) => match profile {
+                    Profile::Duet => Some((x, self.inbox.pop_front())),
+                    _ => {
+                        if r[x] != 0 {
+                            status = Status::Recovered(self.sound);
+                        }
+                        None
+                    },
+                },
+                Jmp(y) => {
+                    offset = r[y];
+                    None
+                },
+                JmpI(y) => {
+                    offset = y;
+                    None
+                },
+                Jgz(x, y) => {
+                    if r[x] > 0 {
+                        offset = r[y];
+                    }
+                    None
+                },
+                JgzI(x, y) => {
+                    if r[x] > 0 {
+                        offset = y;
+                    }
+                    None
+                },
+                Jnz(x, y) => {
+                    if r[x] != 0 {
+                        offset = r[y];
+                    }
+                    None
+                },
+                JnzI(x, y) => {
+                    if r[x] != 0 {
+                        offset = y;
+                    }
+                    None
+                },
+            };
+            match result {
+                Some((x, Some(value))) => r[x] = value,
+                Some((_, None)) => {
+                    break Err(format_err!("instruction {}: `{}` overflowed", pc + 1, self.ops[pc as usize]));
+                },
+                None => {},
+            }
+            pc = pc.saturating_add(offset);
+            steps += 1;
+            if status != Status::Running {
+                break Ok(status);
+            }
+        };
+        self.pc = pc;
+        self.steps = steps;
+        outcome
+    }
+}
+
+fn send(profile: Profile, value: i64, sound: &mut i64, outbox: &mut VecDeque<i64>) -> Option<(usize, Option<i64>)> {
+    match profile {
+        Profile::Duet => outbox.push_back(value),
+        _ => *sound = value,
+    }
+    None
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tablet::parse;
+    use tablet::testing::same_as_vm;
+
+    const SOUND: &str = "set a 1
+add a 2
+mul a a
+mod a 5
+snd a
+set a 0
+rcv a
+jgz a -1
+set a 1
+jgz a -2";
+
+    fn interpret(input: &str, profile: Profile) -> Interpreter {
+        let run = |ops: &[Op]| {
+            let mut interpreter = Interpreter::new(ops, profile).unwrap();
+            (interpreter.run().unwrap(), interpreter)
+        };
+        let (vm, interpreter) = same_as_vm(input, profile, run, Interpreter::get);
+        assert_eq!((vm.pc(), vm.steps()), (interpreter.pc(), interpreter.steps()));
+        interpreter
+    }
+
+    #[test]
+    fn test_lower() {
+        let bytecode = lower(&parse(0, "set b 3\nmul c b\njgz 1 b\njnz 0 5\nsnd 4\njgz c -2").unwrap());
+        assert_eq!(bytecode.names, vec!["b", "c"]);
+        assert_eq!(bytecode.code, vec![
+            Code::SetI(0, 3),
+            Code::Mul(1, 0),
+            Code::Jmp(0),
+            Code::JmpI(1),
+            Code::SndI(4),
+            Code::JgzI(1, -2),
+        ]);
+        assert_eq!(bytecode.slot("c"), Some(1));
+        assert_eq!(bytecode.slot("a"), None);
+    }
+
+    #[test]
+    fn test_sound() {
+        let interpreter = interpret(SOUND, Profile::Sound);
+        assert_eq!(interpreter.get("a"), 1);
+    }
+
+    #[test]
+    fn test_coprocessor() {
+        let ops = parse(0, "set b 3\nmul c b\nsub b 1\njnz b -2\njnz 1 2\nset d 1").unwrap();
+        let mut muls = 0;
+        let mut interpreter = Interpreter::new(&ops, Profile::Coprocessor).unwrap();
+        let status = interpreter.run_with(|code| if let Code::Mul(..) = *code { muls += 1 });
+        assert_eq!(status.unwrap(), Status::Halted);
+        assert_eq!(muls, 3);
+        assert_eq!(interpreter.get("d"), 0);
+        interpret("set b 3\nmul c b\nsub b 1\njnz b -2\njnz 1 2\nset d 1", Profile::Coprocessor);
+    }
+
+    #[test]
+    fn test_duet() {
+        let ops = parse(0, "snd 7\nrcv p\nsnd p").unwrap();
+        let mut interpreter = Interpreter::new(&ops, Profile::Duet).unwrap();
+        interpreter.set("p", 5);
+        interpreter.set("q", 5);
+        assert_eq!(interpreter.run().unwrap(), Status::Blocked);
+        assert_eq!((interpreter.pc(), interpreter.get("p"), interpreter.get("q")), (1, 5, 0));
+        interpreter.inbox.push_back(3);
+        assert_eq!(interpreter.run().unwrap(), Status::Halted);
+        assert_eq!(interpreter.outbox, vec![7, 3]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let ops = |input| parse(0, input).unwrap();
+        assert!(Interpreter::new(&ops("jnz 1 1"), Profile::Sound).is_err());
+
+        let mut interpreter = Interpreter::new(&ops("set a 1\nmod a b"), Profile::Sound).unwrap();
+        assert_eq!(interpreter.run().unwrap_err().to_string(), "instruction 2: modulo by zero");
+        assert_eq!((interpreter.pc(), interpreter.steps()), (1, 1));
+
+        let program = ops("set a 2147483647\nmul a a\nmul a 4");
+        let mut interpreter = Interpreter::new(&program, Profile::Sound).unwrap();
+        assert_eq!(interpreter.run().unwrap_err().to_string(), "instruction 3: `mul a 4` overflowed");
+        let mut interpreter = Interpreter::with_overflow(&program, Profile::Sound, Overflow::Saturating).unwrap();
+        interpreter.run().unwrap();
+        assert_eq!(interpreter.get("a"), i64::MAX);
+    }
+}
@@ -0,0 +1,275 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io::Write;
+
+use shared::AppResult;
+use super::{Reg, Registers, Status, Vm};
+
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Stop {
+    // the vm stopped by itself: halted, blocked or recovered a sound
+    Status(Status),
+    Breakpoint(i64),
+    Watchpoint { reg: Reg, old: i64, new: i64 },
+    Limit(u64),
+}
+
+impl fmt::Display for Stop {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Stop::Status(Status::Running) => write!(f, "running"),
+            Stop::Status(Status::Halted) => write!(f, "halted"),
+            Stop::Status(Status::Blocked) => write!(f, "blocked on rcv"),
+            Stop::Status(Status::Recovered(sound)) => write!(f, "recovered {}", sound),
+            Stop::Breakpoint(pc) => write!(f, "breakpoint at {}", pc),
+            Stop::Watchpoint { ref reg, old, new } => write!(f, "{} changed from {} to {}", reg, old, new),
+            Stop::Limit(steps) => write!(f, "step limit {} reached", steps),
+        }
+    }
+}
+
+
+pub struct Debugger {
+    pub vm: Vm,
+    breakpoints: BTreeSet<i64>,
+    watchpoints: BTreeSet<Reg>,
+    limit: Option<u64>,
+    trace: Option<Box<dyn Write>>,
+}
+
+impl Debugger {
+    pub fn new(vm: Vm) -> Self {
+        Debugger {
+            vm,
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeSet::new(),
+            limit: None,
+            trace: None,
+        }
+    }
+
+    pub fn break_at(&mut self, pc: i64) {
+        self.breakpoints.insert(pc);
+    }
+
+    pub fn clear_break(&mut self, pc: i64) {
+        self.breakpoints.remove(&pc);
+    }
+
+    pub fn watch(&mut self, reg: &str) -> AppResult<()> {
+        self.watchpoints.insert(self.register(reg)?);
+        Ok(())
+    }
+
+    pub fn unwatch(&mut self, reg: &str) {
+        self.watchpoints.remove(reg);
+    }
+
+    fn register(&self, reg: &str) -> AppResult<Reg> {
+        if !self.vm.registers.accepts(reg) {
+            bail!("invalid register `{}`", reg);
+        }
+        Ok(reg.into())
+    }
+
+    // stop once the vm has executed this many instructions in total
+    pub fn limit(&mut self, steps: Option<u64>) {
+        self.limit = steps;
+    }
+
+    // logs every executed instruction as `step pc op` followed by any
+    // registers it changed, e.g. `12 4 add a 2 a=5`
+    pub fn trace_to(&mut self, trace: Box<dyn Write>) {
+        self.trace = Some(trace);
+    }
+
+    fn log(&mut self, step: u64, pc: i64, before: &Registers) -> AppResult<()> {
+        if let Some(ref mut trace) = self.trace {
+            let op = &self.vm.ops()[pc as usize];
+            write!(trace, "{} {} {}", step, pc, op)?;
+            for (reg, value) in self.vm.registers.entries() {
+                if value != before.get(&reg) {
+                    write!(trace, " {}={}", reg, value)?;
+                }
+            }
+            writeln!(trace)?;
+        }
+        Ok(())
+    }
+
+    // executes one instruction, and says why to stop, if there's a reason to
+    pub fn step(&mut self) -> AppResult<Option<Stop>> {
+        let (pc, steps) = (self.vm.pc(), self.vm.steps());
+        let before = self.vm.registers.clone();
+        let status = self.vm.step()?;
+        if self.vm.steps() != steps {
+            self.log(steps, pc, &before)?;
+        }
+
+        if status != Status::Running {
+            return Ok(Some(Stop::Status(status)));
+        }
+        for reg in &self.watchpoints {
+            let (old, new) = (before.get(reg), self.vm.registers.get(reg));
+            if old != new {
+                return Ok(Some(Stop::Watchpoint { reg: reg.clone(), old, new }));
+            }
+        }
+        if self.breakpoints.contains(&self.vm.pc()) {
+            return Ok(Some(Stop::Breakpoint(self.vm.pc())));
+        }
+        match self.limit {
+            Some(limit) if self.vm.steps() >= limit => Ok(Some(Stop::Limit(limit))),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn cont(&mut self) -> AppResult<Stop> {
+        loop {
+            if let Some(stop) = self.step()? {
+                return Ok(stop);
+            }
+        }
+    }
+
+    // where the vm is, e.g. `    4  add a 2`
+    pub fn location(&self) -> String {
+        match self.vm.current() {
+            Some(op) => format!("{:5}  {}", self.vm.pc(), op),
+            None => format!("{:5}  (outside the program)", self.vm.pc()),
+        }
+    }
+
+    // runs one command, returning what to print, or None to quit
+    pub fn execute(&mut self, line: &str) -> AppResult<Option<String>> {
+        let words: Vec<_> = line.split_whitespace().collect();
+        let output = match words.as_slice() {
+            [] => String::new(),
+            ["q"] | ["quit"] => return Ok(None),
+            ["s"] | ["step"] => self.steps(1)?,
+            ["s", count] | ["step", count] => self.steps(count.parse()?)?,
+            ["c"] | ["continue"] => format!("{}\n{}", self.cont()?, self.location()),
+            ["b", pc] | ["break", pc] => {
+                self.break_at(pc.parse()?);
+                format!("breakpoint at {}", pc)
+            },
+            ["delete", pc] => {
+                self.clear_break(pc.parse()?);
+                format!("deleted breakpoint at {}", pc)
+            },
+            ["w", reg] | ["watch", reg] => {
+                self.watch(reg)?;
+                format!("watching {}", reg)
+            },
+            ["unwatch", reg] => {
+                self.unwatch(reg);
+                format!("stopped watching {}", reg)
+            },
+            ["limit", steps] => {
+                self.limit(Some(steps.parse()?));
+                format!("step limit {}", steps)
+            },
+            ["r"] | ["regs"] => format!("{}", self.vm.registers),
+            ["set", reg, value] => {
+                let reg = self.register(reg)?;
+                self.vm.registers.set(&reg, value.parse()?);
+                format!("{}", self.vm.registers)
+            },
+            ["send", value] => {
+                self.vm.inbox.push_back(value.parse()?);
+                format!("inbox: {:?}", self.vm.inbox)
+            },
+            ["where"] => format!("{} (step {})", self.location(), self.vm.steps()),
+            _ => bail!("unknown command `{}`", line.trim()),
+        };
+        Ok(Some(output))
+    }
+
+    fn steps(&mut self, count: u32) -> AppResult<String> {
+        for _ in 0..count {
+            if let Some(stop) = self.step()? {
+                return Ok(format!("{}\n{}", stop, self.location()));
+            }
+        }
+        Ok(self.location())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs::{self, File};
+    use tablet::{Profile, parse};
+
+    const PROGRAM: &str = "set a 3
+add b a
+add a -1
+jgz a -2
+snd b
+rcv b";
+
+    fn debugger() -> Debugger {
+        Debugger::new(Vm::new(&parse(18, PROGRAM).unwrap(), Profile::Sound).unwrap())
+    }
+
+    #[test]
+    fn test_breakpoint() {
+        let mut debugger = debugger();
+        debugger.break_at(3);
+        assert_eq!(debugger.cont().unwrap(), Stop::Breakpoint(3));
+        assert_eq!(debugger.vm.registers.get("b"), 3);
+        assert_eq!(debugger.cont().unwrap(), Stop::Breakpoint(3));
+        debugger.clear_break(3);
+        assert_eq!(debugger.cont().unwrap(), Stop::Status(Status::Recovered(6)));
+    }
+
+    #[test]
+    fn test_watchpoint() {
+        let mut debugger = debugger();
+        debugger.watch("b").unwrap();
+        assert_eq!(debugger.cont().unwrap(), Stop::Watchpoint { reg: "b".into(), old: 0, new: 3 });
+        assert_eq!(debugger.vm.pc(), 2);
+        assert!(debugger.watch("B").is_err());
+    }
+
+    #[test]
+    fn test_limit() {
+        let mut debugger = debugger();
+        debugger.limit(Some(5));
+        assert_eq!(debugger.cont().unwrap(), Stop::Limit(5));
+        assert_eq!(debugger.vm.steps(), 5);
+    }
+
+    #[test]
+    fn test_trace() {
+        let path = env::temp_dir().join(format!("aoc2017-trace-{}.log", ::std::process::id()));
+        {
+            let mut debugger = debugger();
+            debugger.trace_to(Box::new(File::create(&path).unwrap()));
+            debugger.limit(Some(4));
+            debugger.cont().unwrap();
+        }
+        let trace = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(trace, "0 0 set a 3 a=3\n1 1 add b a b=3\n2 2 add a -1 a=2\n3 3 jgz a -2\n");
+    }
+
+    #[test]
+    fn test_execute() {
+        let mut debugger = debugger();
+        assert_eq!(debugger.execute("step 2").unwrap().unwrap(), "    2  add a -1");
+        assert_eq!(debugger.execute("regs").unwrap().unwrap(), "a=3 b=3");
+        assert_eq!(debugger.execute("set a 1").unwrap().unwrap(), "a=1 b=3");
+        assert_eq!(
+            debugger.execute("c").unwrap().unwrap(),
+            "recovered 3\n    6  (outside the program)"
+        );
+        assert!(debugger.execute("frobnicate").is_err());
+        assert!(debugger.execute("watch ab").is_err());
+        assert!(debugger.execute("set A 1").is_err());
+        assert_eq!(debugger.execute("quit").unwrap(), None);
+    }
+}
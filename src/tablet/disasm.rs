@@ -0,0 +1,204 @@
+use std::collections::BTreeSet;
+
+use super::{Op, Target};
+
+
+// where control can go after an instruction
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Exit {
+    // the instruction at this index
+    To(usize),
+    // off either end of the program
+    Halt,
+    // a jump by a register, which could land anywhere
+    Unknown,
+}
+
+fn exit_at(ops: &[Op], index: i64) -> Exit {
+    if index >= 0 && index < ops.len() as i64 {
+        Exit::To(index as usize)
+    } else {
+        Exit::Halt
+    }
+}
+
+fn is_jump(op: &Op) -> bool {
+    matches!(*op, Op::Jgz(..) | Op::Jnz(..))
+}
+
+// the (jump, next) exits of the instruction at `index`. a jump on a constant
+// condition only has one of them
+pub fn exits(ops: &[Op], index: usize) -> (Option<Exit>, Option<Exit>) {
+    let next = exit_at(ops, index as i64 + 1);
+    let (taken, offset) = match ops[index] {
+        Op::Jgz(ref x, ref y) => (x.value().map(|x| x > 0), y),
+        Op::Jnz(ref x, ref y) => (x.value().map(|x| x != 0), y),
+        _ => return (None, Some(next)),
+    };
+    let jump = match *offset {
+        Target::Value(offset) => exit_at(ops, index as i64 + offset),
+        Target::Register(_) => Exit::Unknown,
+    };
+    match taken {
+        Some(true) => (Some(jump), None),
+        Some(false) => (None, Some(next)),
+        None => (Some(jump), Some(next)),
+    }
+}
+
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Block {
+    pub start: usize,
+    // one past the last instruction
+    pub end: usize,
+    pub jump: Option<Exit>,
+    pub next: Option<Exit>,
+}
+
+// blocks start at the program start, at every jump target, and after every
+// jump, so only the last instruction of a block can branch
+pub fn blocks(ops: &[Op]) -> Vec<Block> {
+    let mut leaders = BTreeSet::new();
+    leaders.insert(0);
+    for (index, op) in ops.iter().enumerate() {
+        if let (Some(Exit::To(target)), _) = exits(ops, index) {
+            leaders.insert(target);
+        }
+        if is_jump(op) {
+            leaders.insert(index + 1);
+        }
+    }
+    let starts: Vec<_> = leaders.into_iter().filter(|&i| i < ops.len()).collect();
+    starts.iter()
+        .enumerate()
+        .map(|(n, &start)| {
+            let end = starts.get(n + 1).cloned().unwrap_or(ops.len());
+            let (jump, next) = exits(ops, end - 1);
+            Block { start, end, jump, next }
+        })
+        .collect()
+}
+
+fn label(blocks: &[Block], exit: Exit) -> String {
+    match exit {
+        Exit::To(index) => match blocks.iter().position(|b| b.start == index) {
+            Some(n) => format!("b{}", n),
+            None => format!("{}", index),
+        },
+        Exit::Halt => "halt".into(),
+        Exit::Unknown => "unknown".into(),
+    }
+}
+
+
+// the program split into blocks, with jumps annotated with where they land
+pub fn listing(ops: &[Op]) -> String {
+    let blocks = blocks(ops);
+    let mut out = String::new();
+    for (n, block) in blocks.iter().enumerate() {
+        out.push_str(&format!("b{}:\n", n));
+        for index in block.start..block.end {
+            let op = ops[index].to_string();
+            let note = if !is_jump(&ops[index]) {
+                String::new()
+            } else {
+                match exits(ops, index).0 {
+                    Some(Exit::To(target)) => format!("-> {} ({})", label(&blocks, Exit::To(target)), target),
+                    Some(exit) => format!("-> {}", label(&blocks, exit)),
+                    None => "never taken".into(),
+                }
+            };
+            out.push_str(format!("{:5}  {:<16}{}", index, op, note).trim_end());
+            out.push('\n');
+        }
+    }
+    out
+}
+
+
+// the control-flow graph in Graphviz DOT format
+pub fn dot(ops: &[Op]) -> String {
+    let blocks = blocks(ops);
+    let mut nodes = String::new();
+    let mut edges = String::new();
+    let mut exits = BTreeSet::new();
+    for (n, block) in blocks.iter().enumerate() {
+        let body: String = (block.start..block.end)
+            .map(|index| format!("{}: {}\\l", index, ops[index]))
+            .collect();
+        nodes.push_str(&format!("    b{} [label=\"{}\"];\n", n, body));
+        if let Some(exit) = block.jump {
+            edges.push_str(&format!("    b{} -> {} [label=\"jump\"];\n", n, label(&blocks, exit)));
+            exits.insert(label(&blocks, exit));
+        }
+        if let Some(exit) = block.next {
+            edges.push_str(&format!("    b{} -> {};\n", n, label(&blocks, exit)));
+            exits.insert(label(&blocks, exit));
+        }
+    }
+    for name in &["halt", "unknown"] {
+        if exits.contains(*name) {
+            nodes.push_str(&format!("    {} [shape=oval];\n", name));
+        }
+    }
+    format!(
+        "digraph tablet {{\n    node [shape=box, fontname=\"monospace\"];\n{}{}}}\n",
+        nodes, edges
+    )
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tablet::parse;
+
+    const PROGRAM: &str = "set a 3
+sub a 1
+jnz a -1
+jnz 1 2
+set b 1
+jgz b c";
+
+    #[test]
+    fn test_blocks() {
+        let ops = parse(23, PROGRAM).unwrap();
+        assert_eq!(blocks(&ops), vec![
+            Block {start: 0, end: 1, jump: None, next: Some(Exit::To(1))},
+            Block {start: 1, end: 3, jump: Some(Exit::To(1)), next: Some(Exit::To(3))},
+            Block {start: 3, end: 4, jump: Some(Exit::To(5)), next: None},
+            Block {start: 4, end: 5, jump: None, next: Some(Exit::To(5))},
+            Block {start: 5, end: 6, jump: Some(Exit::Unknown), next: Some(Exit::Halt)},
+        ]);
+        assert_eq!(blocks(&[]), vec![]);
+    }
+
+    #[test]
+    fn test_listing() {
+        let ops = parse(23, PROGRAM).unwrap();
+        assert_eq!(listing(&ops), "b0:
+    0  set a 3
+b1:
+    1  sub a 1
+    2  jnz a -1        -> b1 (1)
+b2:
+    3  jnz 1 2         -> b4 (5)
+b3:
+    4  set b 1
+b4:
+    5  jgz b c         -> unknown
+");
+    }
+
+    #[test]
+    fn test_dot() {
+        let ops = parse(23, PROGRAM).unwrap();
+        let graph = dot(&ops);
+        assert!(graph.starts_with("digraph tablet {\n"));
+        assert!(graph.contains("    b1 [label=\"1: sub a 1\\l2: jnz a -1\\l\"];\n"));
+        assert!(graph.contains("    b1 -> b1 [label=\"jump\"];\n    b1 -> b2;\n"));
+        assert!(graph.contains("    b4 -> halt;\n"));
+        assert!(graph.contains("    unknown [shape=oval];\n"));
+    }
+}
@@ -0,0 +1,294 @@
+use nom::alpha;
+use parsers::{long_integer, parse_lines};
+use shared::AppResult;
+use std::fmt;
+use std::str;
+
+pub mod bytecode;
+pub mod debug;
+pub mod disasm;
+pub mod optimise;
+mod scheduler;
+#[cfg(test)]
+mod testing;
+mod vm;
+
+pub use self::scheduler::Scheduler;
+pub use self::vm::{Config, Overflow, Profile, RegisterFile, Registers, Status, Vm};
+
+
+pub type Reg = String;
+
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Target {
+    Value(i64),
+    Register(Reg),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Op {
+    Snd(Target),
+    Set(Reg, Target),
+    Add(Reg, Target),
+    Sub(Reg, Target),
+    Mul(Reg, Target),
+    Mod(Reg, Target),
+    Rcv(Reg),
+    Jgz(Target, Target),
+    Jnz(Target, Target),
+}
+
+impl Op {
+    // every register the instruction reads or writes
+    pub fn registers(&self) -> Vec<Reg> {
+        use self::Op::*;
+        match *self {
+            Snd(ref x) => x.register().into_iter().cloned().collect(),
+            Set(ref x, ref y) | Add(ref x, ref y) | Sub(ref x, ref y) | Mul(ref x, ref y) | Mod(ref x, ref y) => {
+                Some(x).into_iter().chain(y.register()).cloned().collect()
+            },
+            Rcv(ref x) => vec![x.clone()],
+            Jgz(ref x, ref y) | Jnz(ref x, ref y) => {
+                x.register().into_iter().chain(y.register()).cloned().collect()
+            },
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::Op::*;
+        match *self {
+            Snd(ref x) => write!(f, "snd {}", x),
+            Set(ref x, ref y) => write!(f, "set {} {}", x, y),
+            Add(ref x, ref y) => write!(f, "add {} {}", x, y),
+            Sub(ref x, ref y) => write!(f, "sub {} {}", x, y),
+            Mul(ref x, ref y) => write!(f, "mul {} {}", x, y),
+            Mod(ref x, ref y) => write!(f, "mod {} {}", x, y),
+            Rcv(ref x) => write!(f, "rcv {}", x),
+            Jgz(ref x, ref y) => write!(f, "jgz {} {}", x, y),
+            Jnz(ref x, ref y) => write!(f, "jnz {} {}", x, y),
+        }
+    }
+}
+
+impl Target {
+    pub fn register(&self) -> Option<&Reg> {
+        match *self {
+            Target::Register(ref r) => Some(r),
+            Target::Value(_) => None,
+        }
+    }
+
+    pub fn value(&self) -> Option<i64> {
+        match *self {
+            Target::Value(value) => Some(value),
+            Target::Register(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Target::Value(value) => write!(f, "{}", value),
+            Target::Register(ref reg) => write!(f, "{}", reg),
+        }
+    }
+}
+
+// one instruction per line; `day` is only used in error messages
+pub fn parse(day: u32, input: &str) -> AppResult<Vec<Op>> {
+    parse_lines(day, input, "a tablet instruction", parse_op)
+}
+
+named!(pub parse_op <Op>,
+    alt!(
+        parse_snd |
+        parse_set |
+        parse_add |
+        parse_sub |
+        parse_mul |
+        parse_mod |
+        parse_rcv |
+        parse_jgz |
+        parse_jnz
+    )
+);
+
+named!(register <Reg>,
+    map!(map_res!(alpha, str::from_utf8), String::from)
+);
+
+named!(parse_target <Target>,
+    alt!(
+        register => { Target::Register } |
+        long_integer => { Target::Value }
+    )
+);
+
+named!(parse_target_value <(Reg, Target)>,
+    do_parse!(
+        x: register >>
+        tag!(" ") >>
+        y: parse_target >>
+        ((x, y))
+    )
+);
+
+named!(parse_snd <Op>,
+    map!(preceded!(tag!("snd "), parse_target), |t| Op::Snd(t))
+);
+
+named!(parse_set <Op>,
+    map!(preceded!(tag!("set "), parse_target_value), |tv| Op::Set(tv.0, tv.1))
+);
+
+named!(parse_add <Op>,
+    map!(preceded!(tag!("add "), parse_target_value), |tv| Op::Add(tv.0, tv.1))
+);
+
+named!(parse_sub <Op>,
+    map!(preceded!(tag!("sub "), parse_target_value), |tv| Op::Sub(tv.0, tv.1))
+);
+
+named!(parse_mul <Op>,
+    map!(preceded!(tag!("mul "), parse_target_value), |tv| Op::Mul(tv.0, tv.1))
+);
+
+named!(parse_mod <Op>,
+    map!(preceded!(tag!("mod "), parse_target_value), |tv| Op::Mod(tv.0, tv.1))
+);
+
+named!(parse_rcv <Op>,
+    map!(preceded!(tag!("rcv "), register), Op::Rcv)
+);
+
+named!(parse_jgz <Op>,
+    do_parse!(
+        tag!("jgz ") >>
+        x: parse_target >>
+        tag!(" ") >>
+        y: parse_target >>
+        (Op::Jgz(x, y))
+    )
+);
+
+named!(parse_jnz <Op>,
+    do_parse!(
+        tag!("jnz ") >>
+        x: parse_target >>
+        tag!(" ") >>
+        y: parse_target >>
+        (Op::Jnz(x, y))
+    )
+);
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nom::IResult;
+
+    #[test]
+    fn test_parse_snd() {
+        assert_eq!(
+            parse_snd(&b"snd 5"[..]),
+            IResult::Done(&b""[..], Op::Snd(Target::Value(5)))
+        );
+        assert_eq!(
+            parse_snd(&b"snd a"[..]),
+            IResult::Done(&b""[..], Op::Snd(Target::Register("a".into())))
+        );
+    }
+
+    #[test]
+    fn test_parse_set() {
+        assert_eq!(
+            parse_set(&b"set a 5"[..]),
+            IResult::Done(&b""[..], Op::Set("a".into(), Target::Value(5)))
+        );
+        assert_eq!(
+            parse_set(&b"set a b"[..]),
+            IResult::Done(&b""[..], Op::Set("a".into(), Target::Register("b".into())))
+        );
+        assert_eq!(
+            parse_set(&b"set a -3000000000"[..]),
+            IResult::Done(&b""[..], Op::Set("a".into(), Target::Value(-3_000_000_000)))
+        );
+        assert!(parse(18, "set a 9223372036854775808").is_err());
+    }
+
+    #[test]
+    fn test_parse_wide_register() {
+        assert_eq!(
+            parse_op(&b"set ab cd"[..]),
+            IResult::Done(&b""[..], Op::Set("ab".into(), Target::Register("cd".into())))
+        );
+    }
+
+    #[test]
+    fn test_parser() {
+        use self::Op::*;
+        use self::Target::*;
+
+        let input = "set a 1
+add a 2
+mul a a
+mod a 5
+snd a
+set a 0
+rcv a
+jgz a -1
+set a 1
+jgz a -2";
+        let parsed: Vec<_> = input.split('\n')
+            .filter_map(|line| match parse_op(line.as_bytes()) {
+                IResult::Done(_, p) => Some(p),
+                _ => panic!("parsing failed"),
+            })
+            .collect();
+        assert_eq!(parsed, vec![
+            Set("a".into(), Value(1)),
+            Add("a".into(), Value(2)),
+            Mul("a".into(), Register("a".into())),
+            Mod("a".into(), Value(5)),
+            Snd(Register("a".into())),
+            Set("a".into(), Value(0)),
+            Rcv("a".into()),
+            Jgz(Register("a".into()), Value(-1)),
+            Set("a".into(), Value(1)),
+            Jgz(Register("a".into()), Value(-2)),
+        ]);
+    }
+
+    #[test]
+    fn test_display() {
+        let input = "snd a
+snd -3
+set a b
+add a 2
+sub b -100000
+mul a a
+mod a 5
+rcv a
+jgz 1 -2
+jnz a b
+add ab cd";
+        for line in input.split('\n') {
+            match parse_op(line.as_bytes()) {
+                IResult::Done(_, op) => assert_eq!(op.to_string(), line),
+                _ => panic!("parsing failed"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_registers() {
+        assert_eq!(Op::Set("a".into(), Target::Register("bc".into())).registers(), vec!["a", "bc"]);
+        assert_eq!(Op::Jgz(Target::Value(1), Target::Register("c".into())).registers(), vec!["c"]);
+        assert!(Op::Snd(Target::Value(1)).registers().is_empty());
+    }
+}
@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+
+use super::{Op, Reg, Registers, Target, parse};
+
+
+// a loop idiom replaced by a single step. it only fires when the registers
+// meet its precondition; otherwise the original instructions run as usual
+#[derive(Debug, PartialEq, Clone)]
+pub enum Macro {
+    // `acc += by * counter` (or `-=`), leaving `counter` at 0
+    MulAdd { acc: Reg, by: Target, negate: bool, counter: Reg },
+    // day 23's nested loops: clears `flag` if any `d * e == n` for d and e
+    // in 2..n, leaving `d` and `e` at n and `g` at 0
+    ClearIfComposite { n: Target, flag: Reg, d: Reg, e: Reg, g: Reg },
+}
+
+impl Macro {
+    // number of instructions replaced
+    pub fn span(&self) -> usize {
+        match *self {
+            Macro::MulAdd { .. } => 3,
+            Macro::ClearIfComposite { .. } => 15,
+        }
+    }
+
+    // returns false, changing nothing, if the precondition doesn't hold
+    pub fn apply(&self, registers: &mut Registers) -> bool {
+        match *self {
+            Macro::MulAdd { ref acc, ref by, negate, ref counter } => {
+                let count = registers.get(counter);
+                if count < 1 {
+                    return false;
+                }
+                // every partial sum lies between the first and the last, so
+                // if neither overflows, the loop wouldn't have either
+                let product = registers.resolve(by).checked_mul(if negate { -count } else { count });
+                let value = match product.and_then(|p| registers.get(acc).checked_add(p)) {
+                    Some(value) => value,
+                    None => return false,
+                };
+                registers.set(acc, value);
+                registers.set(counter, 0);
+            },
+            Macro::ClearIfComposite { ref n, ref flag, ref d, ref e, ref g } => {
+                // below 3 the inner loop never meets its exit condition, and
+                // its `d * e` must not overflow
+                let n = registers.resolve(n);
+                if n < 3 || n.checked_mul(n).is_none() {
+                    return false;
+                }
+                if is_composite(n) {
+                    registers.set(flag, 0);
+                }
+                registers.set(d, n);
+                registers.set(e, n);
+                registers.set(g, 0);
+            },
+        }
+        true
+    }
+}
+
+fn is_composite(n: i64) -> bool {
+    (2..).take_while(|div| div * div <= n).any(|div| n % div == 0)
+}
+
+
+// templates use upper case placeholders, which bind to distinct registers
+// (or, where a value is allowed, to any value)
+const MUL_ADD: &str = "add A B
+add C -1
+jgz C -2";
+
+const MUL_SUB: &str = "sub A B
+sub C 1
+jnz C -2";
+
+const CLEAR_IF_COMPOSITE: &str = "set D 2
+set E 2
+set G D
+mul G E
+sub G B
+jnz G 2
+set F 0
+sub E -1
+set G E
+sub G B
+jnz G -8
+sub D -1
+set G D
+sub G B
+jnz G -13";
+
+type Bindings = HashMap<Reg, Target>;
+
+fn bind(bindings: &mut Bindings, placeholder: &Target, actual: &Target) -> bool {
+    match (placeholder, actual) {
+        (&Target::Value(x), &Target::Value(y)) => x == y,
+        (&Target::Value(_), &Target::Register(_)) => false,
+        (Target::Register(p), _) => {
+            if let Some(bound) = bindings.get(p) {
+                return bound == actual;
+            }
+            if actual.register().is_some() && bindings.values().any(|bound| bound == actual) {
+                return false;
+            }
+            bindings.insert(p.clone(), actual.clone());
+            true
+        },
+    }
+}
+
+fn bind_reg(bindings: &mut Bindings, placeholder: &Reg, actual: &Reg) -> bool {
+    bind(bindings, &Target::Register(placeholder.clone()), &Target::Register(actual.clone()))
+}
+
+fn bind_op(bindings: &mut Bindings, template: &Op, op: &Op) -> bool {
+    use super::Op::*;
+    match (template, op) {
+        (Snd(p), Snd(x)) => bind(bindings, p, x),
+        (Set(p, q), Set(x, y)) |
+        (Add(p, q), Add(x, y)) |
+        (Sub(p, q), Sub(x, y)) |
+        (Mul(p, q), Mul(x, y)) |
+        (Mod(p, q), Mod(x, y)) => bind_reg(bindings, p, x) && bind(bindings, q, y),
+        (Rcv(p), Rcv(x)) => bind_reg(bindings, p, x),
+        (Jgz(p, q), Jgz(x, y)) |
+        (Jnz(p, q), Jnz(x, y)) => bind(bindings, p, x) && bind(bindings, q, y),
+        _ => false,
+    }
+}
+
+fn matches(template: &[Op], ops: &[Op]) -> Option<Bindings> {
+    if ops.len() < template.len() {
+        return None;
+    }
+    let mut bindings = HashMap::new();
+    if template.iter().zip(ops).all(|(t, op)| bind_op(&mut bindings, t, op)) {
+        Some(bindings)
+    } else {
+        None
+    }
+}
+
+fn register(bindings: &Bindings, placeholder: &str) -> Reg {
+    bindings[placeholder].register().expect("bound in a register position").clone()
+}
+
+
+// the macro, if any, starting at each instruction
+pub fn macros(ops: &[Op]) -> Vec<Option<Macro>> {
+    let template = |text| parse(0, text).expect("invalid template");
+    let (mul_add, mul_sub, clear) = (template(MUL_ADD), template(MUL_SUB), template(CLEAR_IF_COMPOSITE));
+
+    (0..ops.len())
+        .map(|index| {
+            let ops = &ops[index..];
+            if let Some(b) = matches(&clear, ops) {
+                return Some(Macro::ClearIfComposite {
+                    n: b["B"].clone(),
+                    flag: register(&b, "F"),
+                    d: register(&b, "D"),
+                    e: register(&b, "E"),
+                    g: register(&b, "G"),
+                });
+            }
+            for &(template, negate) in &[(&mul_add, false), (&mul_sub, true)] {
+                if let Some(b) = matches(template, ops) {
+                    return Some(Macro::MulAdd {
+                        acc: register(&b, "A"),
+                        by: b["B"].clone(),
+                        negate,
+                        counter: register(&b, "C"),
+                    });
+                }
+            }
+            None
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tablet::{Profile, Vm};
+    use tablet::testing::same_as_vm;
+
+    // counts the composite numbers in 10..21, the way day 23 does
+    const COMPOSITES: &str = "set b 10
+set c 20
+set f 1
+set d 2
+set e 2
+set g d
+mul g e
+sub g b
+jnz g 2
+set f 0
+sub e -1
+set g e
+sub g b
+jnz g -8
+sub d -1
+set g d
+sub g b
+jnz g -13
+jnz f 2
+sub h -1
+set g b
+sub g c
+jnz g 2
+jnz 1 3
+sub b -1
+jnz 1 -23";
+
+    fn optimised(input: &str, profile: Profile) -> Vm {
+        let run = |ops: &[Op]| {
+            let mut vm = Vm::new(ops, profile).unwrap();
+            vm.optimise();
+            (vm.run().unwrap(), vm)
+        };
+        let (plain, optimised) = same_as_vm(input, profile, run, |vm, reg| vm.registers.get(reg));
+        assert!(optimised.steps() < plain.steps());
+        optimised
+    }
+
+    #[test]
+    fn test_macros() {
+        let found = macros(&parse(0, COMPOSITES).unwrap());
+        assert_eq!(found[3], Some(Macro::ClearIfComposite {
+            n: Target::Register("b".into()),
+            flag: "f".into(),
+            d: "d".into(),
+            e: "e".into(),
+            g: "g".into(),
+        }));
+        assert_eq!(found.iter().filter(|m| m.is_some()).count(), 1);
+
+        let found = macros(&parse(0, "set c 4\nsub a b\nsub c 1\njnz c -2").unwrap());
+        assert_eq!(found[1], Some(Macro::MulAdd {
+            acc: "a".into(), by: Target::Register("b".into()), negate: true, counter: "c".into(),
+        }));
+        // the accumulator and the counter can't be the same register
+        assert_eq!(macros(&parse(0, "sub c b\nsub c 1\njnz c -2").unwrap())[0], None);
+    }
+
+    #[test]
+    fn test_composites() {
+        let vm = optimised(COMPOSITES, Profile::Coprocessor);
+        assert_eq!(vm.registers.get("h"), 7);
+    }
+
+    #[test]
+    fn test_mul_add() {
+        let vm = optimised("set b 3\nset c 4\nadd a b\nadd c -1\njgz c -2", Profile::Sound);
+        assert_eq!(vm.registers.get("a"), 12);
+        assert_eq!(vm.registers.get("c"), 0);
+    }
+
+    #[test]
+    fn test_precondition() {
+        // the counter starts at zero, so the loop only runs once
+        let ops = parse(0, "set b 3\nadd a b\nadd c -1\njgz c -2").unwrap();
+        let mut vm = Vm::new(&ops, Profile::Sound).unwrap();
+        vm.optimise();
+        vm.run().unwrap();
+        assert_eq!(vm.registers.get("a"), 3);
+        assert_eq!(vm.registers.get("c"), -1);
+    }
+
+    #[test]
+    fn test_is_composite() {
+        assert_eq!(
+            (2..20).filter(|&n| !is_composite(n)).collect::<Vec<_>>(),
+            vec![2, 3, 5, 7, 11, 13, 17, 19]
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use shared::AppResult;
+use super::{Status, Vm};
+
+
+// runs programs in turn on one thread, each sending to the next in a ring
+pub struct Scheduler {
+    programs: Vec<Vm>,
+    sent: Vec<u64>,
+}
+
+impl Scheduler {
+    pub fn new(programs: Vec<Vm>) -> Self {
+        let sent = vec![0; programs.len()];
+        Scheduler { programs, sent }
+    }
+
+    pub fn programs(&self) -> &[Vm] {
+        &self.programs
+    }
+
+    // number of values program `index` has sent
+    pub fn sent(&self, index: usize) -> u64 {
+        self.sent[index]
+    }
+
+    // runs until a whole round goes by without any program executing an
+    // instruction, i.e. every program has halted or is blocked on `rcv` with
+    // an empty queue. returns the final status of each program
+    pub fn run(&mut self) -> AppResult<Vec<Status>> {
+        let count = self.programs.len();
+        loop {
+            let mut progress = false;
+            let mut statuses = vec![];
+            for index in 0..count {
+                let before = self.programs[index].steps();
+                statuses.push(self.programs[index].run()?);
+                progress |= self.programs[index].steps() != before;
+
+                let messages: Vec<_> = self.programs[index].outbox.drain(..).collect();
+                self.sent[index] += messages.len() as u64;
+                self.programs[(index + 1) % count].inbox.extend(messages);
+            }
+            if !progress {
+                return Ok(statuses);
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::Op::*;
+    use super::super::{Op, Profile};
+    use super::super::Target::*;
+
+    fn programs(ops: &[Op], count: i64) -> Vec<Vm> {
+        (0..count)
+            .map(|id| {
+                let mut vm = Vm::new(ops, Profile::Duet).unwrap();
+                vm.registers.set("p", id);
+                vm
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_deadlock() {
+        let ops = vec![
+            Snd(Value(1)),
+            Snd(Value(2)),
+            Snd(Register("p".into())),
+            Rcv("a".into()),
+            Rcv("b".into()),
+            Rcv("c".into()),
+            Rcv("d".into()),
+        ];
+        let mut scheduler = Scheduler::new(programs(&ops, 2));
+        assert_eq!(scheduler.run().unwrap(), vec![Status::Blocked, Status::Blocked]);
+        assert_eq!(scheduler.sent(0), 3);
+        assert_eq!(scheduler.sent(1), 3);
+        assert_eq!(scheduler.programs()[0].registers.get("c"), 1);
+    }
+
+    #[test]
+    fn test_ring() {
+        // program 0 starts a counter, which goes once round the ring and
+        // then waits in program 1's queue after everyone has halted
+        let ops = vec![
+            Jgz(Register("p".into()), Value(2)),
+            Snd(Value(0)),
+            Rcv("a".into()),
+            Add("a".into(), Value(1)),
+            Snd(Register("a".into())),
+        ];
+        let mut scheduler = Scheduler::new(programs(&ops, 3));
+        assert_eq!(scheduler.run().unwrap(), vec![Status::Halted; 3]);
+        assert_eq!(scheduler.programs()[1].inbox, vec![3]);
+        assert_eq!((0..3).map(|i| scheduler.sent(i)).collect::<Vec<_>>(), vec![2, 1, 1]);
+    }
+}
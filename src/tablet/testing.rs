@@ -0,0 +1,19 @@
+use super::{parse, Op, Profile, Status, Vm};
+
+
+// runs a program on the plain vm and on another runner, which runs the ops
+// and reads registers back through `get`. both must stop with the same
+// status and the same registers
+pub fn same_as_vm<T, R, G>(input: &str, profile: Profile, run: R, get: G) -> (Vm, T)
+    where R: FnOnce(&[Op]) -> (Status, T), G: Fn(&T, &str) -> i64
+{
+    let ops = parse(0, input).unwrap();
+    let mut vm = Vm::new(&ops, profile).unwrap();
+    let status = vm.run().unwrap();
+    let (other_status, other) = run(&ops);
+    assert_eq!(status, other_status);
+    for (reg, value) in vm.registers.entries() {
+        assert_eq!(get(&other, &reg), value, "register {}", reg);
+    }
+    (vm, other)
+}
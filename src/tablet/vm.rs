@@ -0,0 +1,464 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+
+use shared::AppResult;
+use super::{Op, Reg, Target};
+use super::optimise::{self, Macro};
+
+
+// which meaning `snd` and `rcv` have, and which instructions are allowed
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Profile {
+    // day 18 part 1: `snd` plays a sound, `rcv` recovers the last one played
+    Sound,
+    // day 18 part 2: `snd` and `rcv` pass values between programs
+    Duet,
+    // day 23: no sound, but `sub` and `jnz`
+    Coprocessor,
+}
+
+impl Profile {
+    pub fn supports(self, op: &Op) -> bool {
+        use super::Op::*;
+        match *op {
+            Set(..) | Mul(..) => true,
+            Snd(_) | Add(..) | Mod(..) | Rcv(_) | Jgz(..) => self != Profile::Coprocessor,
+            Sub(..) | Jnz(..) => self == Profile::Coprocessor,
+        }
+    }
+}
+
+
+// what arithmetic does when the result doesn't fit in an i64
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum Overflow {
+    Wrapping,
+    // stops the program with an error
+    #[default]
+    Checked,
+    Saturating,
+}
+
+impl Overflow {
+    pub fn add(self, x: i64, y: i64) -> Option<i64> {
+        match self {
+            Overflow::Wrapping => Some(x.wrapping_add(y)),
+            Overflow::Checked => x.checked_add(y),
+            Overflow::Saturating => Some(x.saturating_add(y)),
+        }
+    }
+
+    pub fn sub(self, x: i64, y: i64) -> Option<i64> {
+        match self {
+            Overflow::Wrapping => Some(x.wrapping_sub(y)),
+            Overflow::Checked => x.checked_sub(y),
+            Overflow::Saturating => Some(x.saturating_sub(y)),
+        }
+    }
+
+    pub fn mul(self, x: i64, y: i64) -> Option<i64> {
+        match self {
+            Overflow::Wrapping => Some(x.wrapping_mul(y)),
+            Overflow::Checked => x.checked_mul(y),
+            Overflow::Saturating => Some(x.saturating_mul(y)),
+        }
+    }
+
+    // only `i64::MIN % -1` overflows in Rust, but its true result, 0, fits,
+    // so every policy gives it. the caller rejects a zero divisor
+    pub fn rem(self, x: i64, y: i64) -> Option<i64> {
+        Some(x.wrapping_rem(y))
+    }
+}
+
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum RegisterFile {
+    // fixed when every register is a single lower case letter, else a map
+    #[default]
+    Auto,
+    Fixed,
+    Map,
+}
+
+
+// all registers start at zero
+#[derive(Debug, PartialEq, Clone)]
+pub enum Registers {
+    // `a` to `z` in an array, for speed
+    Fixed([i64; 26]),
+    // any names
+    Map(BTreeMap<Reg, i64>),
+}
+
+impl Registers {
+    pub fn new(file: RegisterFile, ops: &[Op]) -> AppResult<Self> {
+        let fits = |op: &Op| op.registers().iter().all(|r| Registers::index(r).is_some());
+        match file {
+            RegisterFile::Map => Ok(Registers::Map(BTreeMap::new())),
+            RegisterFile::Auto if !ops.iter().all(fits) => Ok(Registers::Map(BTreeMap::new())),
+            RegisterFile::Auto | RegisterFile::Fixed => {
+                if let Some(index) = ops.iter().position(|op| !fits(op)) {
+                    bail!("instruction {}: `{}` needs registers other than a to z", index + 1, ops[index]);
+                }
+                Ok(Registers::Fixed([0; 26]))
+            },
+        }
+    }
+
+    // the slot of a register in the fixed file
+    pub fn index(reg: &str) -> Option<usize> {
+        match reg.as_bytes() {
+            &[r] if r.is_ascii_lowercase() => Some((r - b'a') as usize),
+            _ => None,
+        }
+    }
+
+    // whether the register can be stored in this file
+    pub fn accepts(&self, reg: &str) -> bool {
+        match *self {
+            Registers::Fixed(_) => Registers::index(reg).is_some(),
+            Registers::Map(_) => !reg.is_empty() && reg.chars().all(char::is_alphabetic),
+        }
+    }
+
+    pub fn get(&self, reg: &str) -> i64 {
+        match *self {
+            Registers::Fixed(ref values) => values[Registers::index(reg).expect("invalid register")],
+            Registers::Map(ref values) => values.get(reg).cloned().unwrap_or(0),
+        }
+    }
+
+    pub fn set(&mut self, reg: &str, value: i64) {
+        match *self {
+            Registers::Fixed(ref mut values) => {
+                values[Registers::index(reg).expect("invalid register")] = value;
+            },
+            Registers::Map(ref mut values) => match values.get_mut(reg) {
+                Some(slot) => *slot = value,
+                None => {
+                    values.insert(reg.into(), value);
+                },
+            },
+        }
+    }
+
+    pub fn resolve(&self, target: &Target) -> i64 {
+        match *target {
+            Target::Value(value) => value,
+            Target::Register(ref reg) => self.get(reg),
+        }
+    }
+
+    // every register that may have been set, in name order
+    pub fn entries(&self) -> Vec<(Reg, i64)> {
+        match *self {
+            Registers::Fixed(ref values) => values
+                .iter()
+                .zip(b'a'..)
+                .map(|(&value, reg)| ((reg as char).to_string(), value))
+                .collect(),
+            Registers::Map(ref values) => values.iter().map(|(reg, &value)| (reg.clone(), value)).collect(),
+        }
+    }
+}
+
+impl Default for Registers {
+    fn default() -> Self {
+        Registers::Fixed([0; 26])
+    }
+}
+
+
+// the registers that aren't zero, e.g. `a=1 p=5`
+impl fmt::Display for Registers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let set: Vec<_> = self.entries()
+            .into_iter()
+            .filter(|&(_, value)| value != 0)
+            .map(|(reg, value)| format!("{}={}", reg, value))
+            .collect();
+        write!(f, "{}", set.join(" "))
+    }
+}
+
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Config {
+    pub registers: RegisterFile,
+    pub overflow: Overflow,
+}
+
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Status {
+    Running,
+    // the program counter left the program
+    Halted,
+    // `rcv` with nothing in the inbox; stepping again retries it
+    Blocked,
+    // `rcv` of a non-zero register, with the last sound played
+    Recovered(i64),
+}
+
+
+pub struct Vm {
+    profile: Profile,
+    overflow: Overflow,
+    ops: Vec<Op>,
+    macros: Vec<Option<Macro>>,
+    pc: i64,
+    steps: u64,
+    sound: i64,
+    pub registers: Registers,
+    pub inbox: VecDeque<i64>,
+    pub outbox: VecDeque<i64>,
+}
+
+impl Vm {
+    pub fn new(ops: &[Op], profile: Profile) -> AppResult<Self> {
+        Vm::with_config(ops, profile, Config::default())
+    }
+
+    pub fn with_config(ops: &[Op], profile: Profile, config: Config) -> AppResult<Self> {
+        if let Some(index) = ops.iter().position(|op| !profile.supports(op)) {
+            bail!("instruction {}: `{}` is not part of the {:?} instruction set", index + 1, ops[index], profile);
+        }
+        Ok(Vm {
+            profile,
+            overflow: config.overflow,
+            ops: ops.to_vec(),
+            macros: vec![],
+            pc: 0,
+            steps: 0,
+            sound: 0,
+            registers: Registers::new(config.registers, ops)?,
+            inbox: VecDeque::new(),
+            outbox: VecDeque::new(),
+        })
+    }
+
+    // replaces known loop idioms with macro-ops
+    pub fn optimise(&mut self) {
+        self.macros = optimise::macros(&self.ops);
+    }
+
+    pub fn ops(&self) -> &[Op] {
+        &self.ops
+    }
+
+    // the instruction about to run, if the program hasn't halted
+    pub fn current(&self) -> Option<&Op> {
+        if self.pc < 0 {
+            return None;
+        }
+        self.ops.get(self.pc as usize)
+    }
+
+    pub fn pc(&self) -> i64 {
+        self.pc
+    }
+
+    // number of instructions executed so far
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn step(&mut self) -> AppResult<Status> {
+        self.step_with(&mut |_| {})
+    }
+
+    // `hook` sees each instruction just before it is executed. a macro-op
+    // counts as one step, and the hook doesn't see the instructions it covers
+    pub fn step_with<F: FnMut(&Op)>(&mut self, hook: &mut F) -> AppResult<Status> {
+        use super::Op::*;
+
+        if self.pc < 0 || self.pc >= self.ops.len() as i64 {
+            return Ok(Status::Halted);
+        }
+        if let Some(Some(m)) = self.macros.get(self.pc as usize) {
+            if m.apply(&mut self.registers) {
+                self.pc += m.span() as i64;
+                self.steps += 1;
+                return Ok(Status::Running);
+            }
+        }
+        let op = &self.ops[self.pc as usize];
+        if let Rcv(_) = *op {
+            if self.profile == Profile::Duet && self.inbox.is_empty() {
+                return Ok(Status::Blocked);
+            }
+        }
+        hook(op);
+
+        let (pc, overflow) = (self.pc, self.overflow);
+        let arithmetic = |result: Option<i64>| {
+            result.ok_or_else(|| format_err!("instruction {}: `{}` overflowed", pc + 1, op))
+        };
+        let mut status = Status::Running;
+        let mut offset = 1;
+        match *op {
+            Snd(ref x) => {
+                let value = self.registers.resolve(x);
+                match self.profile {
+                    Profile::Duet => self.outbox.push_back(value),
+                    _ => self.sound = value,
+                }
+            },
+            Set(ref x, ref y) => {
+                let value = self.registers.resolve(y);
+                self.registers.set(x, value);
+            },
+            Add(ref x, ref y) => {
+                let value = arithmetic(overflow.add(self.registers.get(x), self.registers.resolve(y)))?;
+                self.registers.set(x, value);
+            },
+            Sub(ref x, ref y) => {
+                let value = arithmetic(overflow.sub(self.registers.get(x), self.registers.resolve(y)))?;
+                self.registers.set(x, value);
+            },
+            Mul(ref x, ref y) => {
+                let value = arithmetic(overflow.mul(self.registers.get(x), self.registers.resolve(y)))?;
+                self.registers.set(x, value);
+            },
+            Mod(ref x, ref y) => {
+                let divisor = self.registers.resolve(y);
+                if divisor == 0 {
+                    bail!("instruction {}: modulo by zero", self.pc + 1);
+                }
+                let value = arithmetic(overflow.rem(self.registers.get(x), divisor))?;
+                self.registers.set(x, value);
+            },
+            Rcv(ref x) => match self.profile {
+                Profile::Duet => {
+                    let value = self.inbox.pop_front().expect("inbox checked above");
+                    self.registers.set(x, value);
+                },
+                _ => if self.registers.get(x) != 0 {
+                    status = Status::Recovered(self.sound);
+                },
+            },
+            Jgz(ref x, ref y) => if self.registers.resolve(x) > 0 {
+                offset = self.registers.resolve(y);
+            },
+            Jnz(ref x, ref y) => if self.registers.resolve(x) != 0 {
+                offset = self.registers.resolve(y);
+            },
+        }
+        self.pc = self.pc.saturating_add(offset);
+        self.steps += 1;
+        Ok(status)
+    }
+
+    pub fn run(&mut self) -> AppResult<Status> {
+        self.run_with(|_| {})
+    }
+
+    // runs until the program halts, blocks or recovers a sound
+    pub fn run_with<F: FnMut(&Op)>(&mut self, mut hook: F) -> AppResult<Status> {
+        loop {
+            match self.step_with(&mut hook)? {
+                Status::Running => {},
+                status => return Ok(status),
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::parse;
+
+    fn vm(input: &str, profile: Profile) -> Vm {
+        Vm::new(&parse(0, input).unwrap(), profile).unwrap()
+    }
+
+    fn overflow(overflow: Overflow) -> AppResult<i64> {
+        let ops = parse(0, "set a 2147483647\nmul a a\nmul a 4").unwrap();
+        let config = Config { overflow, ..Config::default() };
+        let mut vm = Vm::with_config(&ops, Profile::Sound, config)?;
+        vm.run()?;
+        Ok(vm.registers.get("a"))
+    }
+
+    #[test]
+    fn test_sound() {
+        let mut vm = vm("set a 1
+add a 2
+mul a a
+mod a 5
+snd a
+set a 0
+rcv a
+jgz a -1
+set a 1
+jgz a -2", Profile::Sound);
+        assert_eq!(vm.run().unwrap(), Status::Recovered(4));
+        assert_eq!(vm.steps(), 12);
+        assert_eq!(vm.registers.to_string(), "a=1");
+        assert_eq!(vm.current().map(|op| op.to_string()), Some("jgz a -1".into()));
+
+        let mut wide = Vm::new(&parse(0, "set a 3000000000\nsnd a\nrcv a").unwrap(), Profile::Sound).unwrap();
+        assert_eq!(wide.run().unwrap(), Status::Recovered(3_000_000_000));
+    }
+
+    #[test]
+    fn test_duet() {
+        let mut vm = vm("snd 7\nrcv a\nsnd a", Profile::Duet);
+        assert_eq!(vm.run().unwrap(), Status::Blocked);
+        assert_eq!(vm.pc(), 1);
+        vm.inbox.push_back(3);
+        assert_eq!(vm.run().unwrap(), Status::Halted);
+        assert_eq!(vm.outbox, vec![7, 3]);
+    }
+
+    #[test]
+    fn test_coprocessor() {
+        let mut vm = vm("set b 3\nmul c b\nsub b 1\njnz b -2", Profile::Coprocessor);
+        let mut muls = 0;
+        assert_eq!(vm.run_with(|op| if let Op::Mul(..) = *op { muls += 1 }).unwrap(), Status::Halted);
+        assert_eq!(muls, 3);
+        assert_eq!(vm.registers.get("b"), 0);
+        assert_eq!(vm.current(), None);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let ops = |input| parse(0, input).unwrap();
+        assert!(Vm::new(&ops("jnz 1 1"), Profile::Sound).is_err());
+        assert!(Vm::new(&ops("snd 1"), Profile::Coprocessor).is_err());
+        let config = Config { registers: RegisterFile::Fixed, ..Config::default() };
+        assert!(Vm::with_config(&ops("set A 1"), Profile::Duet, config).is_err());
+        assert!(vm("mod a b", Profile::Sound).step().is_err());
+    }
+
+    #[test]
+    fn test_register_files() {
+        let mut vm = vm("set ab 2\nadd ab ab\nset a 1", Profile::Sound);
+        vm.run().unwrap();
+        assert_eq!(vm.registers.get("ab"), 4);
+        assert_eq!(vm.registers.to_string(), "a=1 ab=4");
+        assert!(vm.registers.accepts("Ab"));
+
+        let ops = parse(0, "set a 1").unwrap();
+        let config = Config { registers: RegisterFile::Map, ..Config::default() };
+        let vm = Vm::with_config(&ops, Profile::Sound, config).unwrap();
+        assert_eq!(vm.registers, Registers::Map(BTreeMap::new()));
+        assert_eq!(Vm::new(&ops, Profile::Sound).unwrap().registers, Registers::default());
+        assert!(!Registers::default().accepts("ab"));
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert!(overflow(Overflow::Checked).is_err());
+        assert_eq!(overflow(Overflow::Saturating).unwrap(), i64::MAX);
+        assert_eq!(overflow(Overflow::Wrapping).unwrap(), (2147483647i64 * 2147483647).wrapping_mul(4));
+        assert_eq!(Overflow::Checked.rem(i64::MIN, -1), Some(0));
+        assert_eq!(Overflow::Wrapping.rem(i64::MIN, -1), Some(0));
+        let mut vm = vm("set a -9223372036854775808\nmod a -1", Profile::Sound);
+        assert_eq!(vm.run().unwrap(), Status::Halted);
+        assert_eq!(vm.registers.get("a"), 0);
+    }
+}